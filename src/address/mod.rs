@@ -20,6 +20,28 @@ impl AddressDiffAmount for u32 {}
 impl AddressDiffAmount for u16 {}
 impl AddressDiffAmount for usize {}
 
+/// an `AddressDiffAmount` that can be built from a count of words. `Reader`s report offsets as a
+/// number of words read, so `U8Reader` (and `ReaderBuilder` for `&[u8]`) can be used with any
+/// `AddressBase` whose `Diff` implements this.
+///
+/// counts that do not fit in `Self` wrap, as converting with `as` would.
+pub trait FromWordCount {
+    fn from_word_count(count: usize) -> Self;
+}
+
+impl FromWordCount for u64 {
+    fn from_word_count(count: usize) -> Self { count as u64 }
+}
+impl FromWordCount for u32 {
+    fn from_word_count(count: usize) -> Self { count as u32 }
+}
+impl FromWordCount for u16 {
+    fn from_word_count(count: usize) -> Self { count as u16 }
+}
+impl FromWordCount for usize {
+    fn from_word_count(count: usize) -> Self { count }
+}
+
 /// a struct describing the differece between some pair of `A: Address`. this is primarily useful
 /// in describing the size of an instruction, or the relative offset of a branch.
 ///
@@ -64,7 +86,7 @@ pub struct AddressDiff<T: AddressBase> {
     amount: T::Diff,
 }
 
-impl<T: Address> AddressDiff<T> {
+impl<T: AddressBase> AddressDiff<T> {
     pub fn from_const(amount: T::Diff) -> Self {
         AddressDiff { amount }
     }
@@ -73,13 +95,13 @@ impl<T: Address> AddressDiff<T> {
     }
 }
 
-impl<T: Address> fmt::Debug for AddressDiff<T> where T::Diff: fmt::Debug {
+impl<T: AddressBase> fmt::Debug for AddressDiff<T> where T::Diff: fmt::Debug {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "AddressDiff({:?})", self.amount)
     }
 }
 
-impl<T: Address> AddressDiff<T> {
+impl<T: AddressBase> AddressDiff<T> {
     pub fn one() -> Self {
         AddressDiff {
             amount: <T as AddressBase>::Diff::one(),
@@ -0,0 +1,47 @@
+//! shims for code written against earlier versions of `yaxpeax-arch`.
+//!
+//! items here are deprecated as soon as they are added, and exist to give decoder crates time to
+//! migrate rather than break outright. they will be removed in a future release.
+
+#![allow(deprecated)]
+
+use crate::{AddressBase, ReadError};
+
+/// the `Reader` interface as of `yaxpeax-arch` 0.2, where `offset` and `total_offset` returned a
+/// bare `Address`.
+///
+/// this trait is implemented for every [`crate::Reader`]. a decoder written against 0.2 can
+/// import `yaxpeax_arch::compat::Reader` in place of `yaxpeax_arch::Reader` and continue to compile
+/// unchanged, with deprecation warnings pointing at what needs to be ported. `Decoder::decode_into`
+/// impls may keep their `T: Reader<A::Address, A::Word>` bound with this trait; it is implied by
+/// the bound `Decoder` declares.
+#[deprecated(since = "0.3.0", note = "`yaxpeax_arch::Reader::offset` and `total_offset` now return an `AddressDiff`. use `yaxpeax_arch::Reader` and convert with `AddressDiff::to_const` where a number is needed")]
+pub trait Reader<Address: AddressBase, Item> {
+    fn next(&mut self) -> Result<Item, ReadError>;
+    fn next_n(&mut self, buf: &mut [Item]) -> Result<(), ReadError>;
+    fn mark(&mut self);
+    /// the difference, in `Address`, between the current `Reader` position and its last `mark`,
+    /// as an `Address`.
+    fn offset(&mut self) -> Address;
+    /// the difference, in `Address`, between the current `Reader` position and the initial offset
+    /// when constructed, as an `Address`.
+    fn total_offset(&mut self) -> Address;
+}
+
+impl<Address: AddressBase, Item, T: crate::Reader<Address, Item>> Reader<Address, Item> for T {
+    fn next(&mut self) -> Result<Item, ReadError> {
+        crate::Reader::next(self)
+    }
+    fn next_n(&mut self, buf: &mut [Item]) -> Result<(), ReadError> {
+        crate::Reader::next_n(self, buf)
+    }
+    fn mark(&mut self) {
+        crate::Reader::mark(self)
+    }
+    fn offset(&mut self) -> Address {
+        Address::zero() + crate::Reader::offset(self)
+    }
+    fn total_offset(&mut self) -> Address {
+        Address::zero() + crate::Reader::total_offset(self)
+    }
+}
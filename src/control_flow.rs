@@ -0,0 +1,51 @@
+use crate::AddressBase;
+
+/// how an instruction affects where execution goes next, as reported by
+/// [`ControlFlow::effect`].
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum Effect {
+    /// execution continues with the next instruction.
+    FallThrough,
+    /// execution continues at a target, such as with a jump.
+    Branch,
+    /// execution continues at a target, and is expected to return to the next instruction.
+    Call,
+    /// execution returns to a caller.
+    Return,
+    /// execution traps or enters a more privileged mode: system calls, breakpoints, undefined
+    /// instruction traps and so on.
+    Trap,
+}
+
+/// control-flow facts about an instruction, for analyses that should not need to know which
+/// architecture they are looking at: control-flow graph recovery, call graphs, gadget search.
+///
+/// architectures implement this for their `Arch::Instruction`, with `Address` being their
+/// `Arch::Address`. generic code can then ask for it with a bound like
+/// `A::Instruction: ControlFlow<Address = A::Address>`.
+pub trait ControlFlow {
+    type Address: AddressBase;
+
+    /// what kind of control flow this instruction performs. for conditional instructions, this is
+    /// the effect when the condition holds.
+    fn effect(&self) -> Effect;
+
+    /// does the effect of this instruction depend on a condition? a conditional branch either
+    /// branches or falls through, a conditional return either returns or falls through, and so on.
+    fn is_conditional(&self) -> bool;
+
+    /// where this instruction, located at `address`, transfers control to, if that is known from
+    /// the instruction alone. this is `None` for instructions that fall through, and for indirect
+    /// branches, calls and returns, whose targets depend on register or memory contents.
+    fn target(&self, address: Self::Address) -> Option<Self::Address>;
+
+    /// can execution continue with the next instruction after this one? by default this is true
+    /// for instructions that fall through, calls, traps, and conditional instructions of any kind.
+    /// architectures should override this for traps that never return, such as x86's `ud2`.
+    fn falls_through(&self) -> bool {
+        match self.effect() {
+            Effect::FallThrough | Effect::Call | Effect::Trap => true,
+            Effect::Branch | Effect::Return => self.is_conditional(),
+        }
+    }
+}
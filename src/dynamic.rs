@@ -0,0 +1,456 @@
+//! object-safe decoding, for choosing an architecture at runtime.
+//!
+//! `Arch` and `Decoder` are generic throughout, which is what lets decoders be fast, but means
+//! they cannot be used as trait objects. [`DynDecoder`] decodes from bytes into boxed
+//! [`DynInstruction`]s and reports [`DynDecodeError`]s, with no type parameters in sight, and
+//! [`ArchDecoder`] implements it for any `Arch` whose words a `U8Reader` can read. bytes come from
+//! a [`DynReader`], which any `Reader<u64, u8>` can be used as.
+
+use core::any::Any;
+use core::fmt;
+
+use std::boxed::Box;
+use std::vec::Vec;
+
+use num_traits::Zero;
+
+use crate::{AddressBase, AddressDiff, Arch, DecodeError, Decoder, Endianness, FromWordCount, Instruction};
+use crate::{LengthedInstruction, Reader, ReadError, SliceReader, U8Reader};
+use crate::{U16bi, U32bi, U64bi, U16le, U16be, U24le, U24be, U32le, U32be, U32pdp, U64le, U64be};
+use crate::reader::FromBytes;
+
+/// an instruction of an architecture chosen at runtime.
+// instructions are never empty, so there is no `is_empty` to go with `len`.
+#[allow(clippy::len_without_is_empty)]
+pub trait DynInstruction: fmt::Display + fmt::Debug {
+    /// as `LengthedInstruction::len`: the length of this instruction, in units of the
+    /// architecture's addresses.
+    fn len(&self) -> usize;
+    /// as `Instruction::well_defined`.
+    fn well_defined(&self) -> bool;
+    /// the concrete `Arch::Instruction`, for callers that know which architecture decoded it.
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// a decoder for an architecture chosen at runtime.
+pub trait DynDecoder {
+    /// decode one instruction from `words`, leaving `words` after it as `Decoder::decode` would.
+    fn decode(&self, words: &mut dyn DynReader) -> Result<Box<dyn DynInstruction>, DynDecodeError>;
+
+    /// decode one instruction from the start of `data`.
+    fn decode_slice(&self, data: &[u8]) -> Result<Box<dyn DynInstruction>, DynDecodeError> {
+        self.decode(&mut U8Reader::new(data))
+    }
+}
+
+/// an object-safe reader of bytes, for a [`DynDecoder`] to decode from. this is implemented for
+/// every `Reader<u64, u8>` with `'static` checkpoints, which includes this crate's byte readers:
+/// `U8Reader`s, `IoReader`s, `MemoryMapReader`s, `StreamReader`s and so on can all be passed to
+/// `DynDecoder::decode` as they are. `BitReader` and `PackedWordReader` do not read bytes, so they
+/// are not `DynReader`s.
+///
+/// offsets are counted in bytes. methods are as on `Reader`, except that checkpoints are boxed.
+pub trait DynReader {
+    /// as `Reader::next`.
+    fn next(&mut self) -> Result<u8, ReadError>;
+    /// as `Reader::next_n`.
+    fn next_n(&mut self, buf: &mut [u8]) -> Result<(), ReadError>;
+    /// as `Reader::peek`.
+    fn peek(&mut self) -> Result<u8, ReadError>;
+    /// as `Reader::peek_n`.
+    fn peek_n(&mut self, buf: &mut [u8]) -> Result<(), ReadError>;
+    /// as `Reader::mark`.
+    fn mark(&mut self);
+    /// as `Reader::rewind_to_mark`.
+    fn rewind_to_mark(&mut self);
+    /// as `Reader::checkpoint`, with the reader's checkpoint boxed up.
+    fn checkpoint(&mut self) -> Box<dyn Any>;
+    /// as `Reader::restore`. a checkpoint from a different type of reader fails with
+    /// `ReadError::InvalidCheckpoint`.
+    fn restore(&mut self, checkpoint: &dyn Any) -> Result<(), ReadError>;
+    /// as `Reader::offset`, in bytes.
+    fn offset(&mut self) -> u64;
+    /// as `Reader::total_offset`, in bytes.
+    fn total_offset(&mut self) -> u64;
+    /// as `Reader::as_slice_reader`.
+    fn as_slice_reader(&mut self) -> Option<&mut dyn SliceReader>;
+}
+
+impl<R: Reader<u64, u8>> DynReader for R where R::Checkpoint: 'static {
+    fn next(&mut self) -> Result<u8, ReadError> { Reader::next(self) }
+    fn next_n(&mut self, buf: &mut [u8]) -> Result<(), ReadError> { Reader::next_n(self, buf) }
+    fn peek(&mut self) -> Result<u8, ReadError> { Reader::peek(self) }
+    fn peek_n(&mut self, buf: &mut [u8]) -> Result<(), ReadError> { Reader::peek_n(self, buf) }
+    fn mark(&mut self) { Reader::mark(self) }
+    fn rewind_to_mark(&mut self) { Reader::rewind_to_mark(self) }
+    fn checkpoint(&mut self) -> Box<dyn Any> { Box::new(Reader::checkpoint(self)) }
+    fn restore(&mut self, checkpoint: &dyn Any) -> Result<(), ReadError> {
+        match checkpoint.downcast_ref::<R::Checkpoint>() {
+            Some(checkpoint) => Reader::restore(self, *checkpoint),
+            None => Err(ReadError::InvalidCheckpoint),
+        }
+    }
+    fn offset(&mut self) -> u64 { Reader::offset(self).to_const() }
+    fn total_offset(&mut self) -> u64 { Reader::total_offset(self).to_const() }
+    fn as_slice_reader(&mut self) -> Option<&mut dyn SliceReader> { Reader::as_slice_reader(self) }
+}
+
+/// a saved position of a [`DynWordReader`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct DynCheckpoint {
+    index: usize,
+}
+
+/// a `Reader` of words from a [`DynReader`], for `ArchDecoder` to give its `Arch::Decoder`.
+/// `DynWordReader` implements `Reader` for all words `U8Reader` supports, with offsets counted in
+/// words as `U8Reader` does.
+///
+/// the boxed checkpoints of the underlying `DynReader` are kept until the `DynWordReader` is
+/// dropped, so a `DynWordReader` is meant to last for one instruction.
+pub struct DynWordReader<'a> {
+    inner: &'a mut dyn DynReader,
+    endianness: Endianness,
+    checkpoints: Vec<Box<dyn Any>>,
+}
+
+impl<'a> DynWordReader<'a> {
+    /// create a reader of words from `inner`, reading bi-endian words in the byte order
+    /// `endianness`.
+    pub fn new(inner: &'a mut dyn DynReader, endianness: Endianness) -> DynWordReader<'a> {
+        DynWordReader {
+            inner,
+            endianness,
+            checkpoints: Vec::new(),
+        }
+    }
+
+    fn peek_word<W: FromBytes>(&mut self) -> Result<W, ReadError> {
+        let mut bytes = [0u8; 8];
+        let bytes = &mut bytes[..W::SIZE];
+        self.inner.peek_n(bytes).map_err(|e| in_words(e, 0, 1))?;
+        Ok(W::from_bytes(bytes))
+    }
+
+    fn read_word<W: FromBytes>(&mut self) -> Result<W, ReadError> {
+        let word = self.peek_word()?;
+        // the word's bytes were just peeked, so they are there to be read.
+        self.inner.next_n(&mut [0u8; 8][..W::SIZE])?;
+        Ok(word)
+    }
+
+    /// read words into `buf` without consuming them, until it is full or the underlying reader
+    /// fails.
+    fn peek_words<W: FromBytes>(&mut self, buf: &mut [W]) -> Result<(), ReadError> {
+        let mut stack = [0u8; STACK_BYTES];
+        let mut heap = Vec::new();
+        let bytes = byte_buffer(&mut stack, &mut heap, buf.len() * W::SIZE);
+        self.peek_words_via(buf, bytes)
+    }
+
+    fn read_words<W: FromBytes>(&mut self, buf: &mut [W]) -> Result<(), ReadError> {
+        let mut stack = [0u8; STACK_BYTES];
+        let mut heap = Vec::new();
+        let bytes = byte_buffer(&mut stack, &mut heap, buf.len() * W::SIZE);
+        let res = self.peek_words_via(buf, bytes);
+        // the whole words were just peeked, so they are there to be read; a trailing fraction of
+        // a word is left unread.
+        let count = words_read(&res, buf.len());
+        self.inner.next_n(&mut bytes[..count * W::SIZE])?;
+        res
+    }
+
+    /// `peek_words`, peeking bytes into `bytes`, which holds exactly as many bytes as `buf` does.
+    fn peek_words_via<W: FromBytes>(&mut self, buf: &mut [W], bytes: &mut [u8]) -> Result<(), ReadError> {
+        let res = self.inner.peek_n(bytes);
+        let count = match &res {
+            Ok(()) => buf.len(),
+            Err(e) => e.items_read().unwrap_or(0) / W::SIZE,
+        };
+        for (word, bytes) in buf[..count].iter_mut().zip(bytes.chunks_exact(W::SIZE)) {
+            *word = W::from_bytes(bytes);
+        }
+        res.map_err(|e| in_words(e, count, buf.len() - count))
+    }
+}
+
+/// reads of up to this many bytes are buffered on the stack, rather than allocating.
+const STACK_BYTES: usize = 64;
+
+/// a buffer of `len` bytes: the start of `stack` if it is long enough, `heap` grown to `len`
+/// otherwise.
+fn byte_buffer<'b>(stack: &'b mut [u8; STACK_BYTES], heap: &'b mut Vec<u8>, len: usize) -> &'b mut [u8] {
+    if len <= STACK_BYTES {
+        &mut stack[..len]
+    } else {
+        heap.resize(len, 0);
+        &mut heap[..]
+    }
+}
+
+/// how many of `len` words a read that returned `res` filled in.
+fn words_read(res: &Result<(), ReadError>, len: usize) -> usize {
+    match res {
+        Ok(()) => len,
+        Err(e) => e.items_read().unwrap_or(0),
+    }
+}
+
+/// `e`, from a read of bytes, as the error of a read of words that read `read` words and needed
+/// `shortfall` more.
+fn in_words(e: ReadError, read: usize, shortfall: usize) -> ReadError {
+    match e {
+        ReadError::Incomplete { .. } => ReadError::Incomplete { read, shortfall },
+        ReadError::Unmapped { address, .. } => ReadError::Unmapped { address, read, shortfall },
+        e => e,
+    }
+}
+
+// `$in_order` puts a word parsed by `FromBytes` into the reader's byte order; that only changes
+// bi-endian words.
+macro_rules! dyn_word_reader_impl {
+    ($word:ident) => {
+        dyn_word_reader_impl!($word, |word: $word, _: Endianness| word);
+    };
+    ($word:ident, $in_order:expr) => {
+        impl<A: AddressBase> Reader<A, $word> for DynWordReader<'_> where A::Diff: FromWordCount {
+            type Checkpoint = DynCheckpoint;
+
+            #[inline]
+            fn next(&mut self) -> Result<$word, ReadError> {
+                self.read_word().map(|word| $in_order(word, self.endianness))
+            }
+            #[inline]
+            fn next_n(&mut self, buf: &mut [$word]) -> Result<(), ReadError> {
+                let res = self.read_words(buf);
+                let count = words_read(&res, buf.len());
+                for word in buf[..count].iter_mut() {
+                    *word = $in_order(*word, self.endianness);
+                }
+                res
+            }
+            #[inline]
+            fn peek(&mut self) -> Result<$word, ReadError> {
+                self.peek_word().map(|word| $in_order(word, self.endianness))
+            }
+            #[inline]
+            fn peek_n(&mut self, buf: &mut [$word]) -> Result<(), ReadError> {
+                let res = self.peek_words(buf);
+                let count = words_read(&res, buf.len());
+                for word in buf[..count].iter_mut() {
+                    *word = $in_order(*word, self.endianness);
+                }
+                res
+            }
+            #[inline]
+            fn mark(&mut self) {
+                self.inner.mark();
+            }
+            #[inline]
+            fn rewind_to_mark(&mut self) {
+                self.inner.rewind_to_mark();
+            }
+            #[inline]
+            fn checkpoint(&mut self) -> DynCheckpoint {
+                self.checkpoints.push(self.inner.checkpoint());
+                DynCheckpoint { index: self.checkpoints.len() - 1 }
+            }
+            #[inline]
+            fn restore(&mut self, checkpoint: DynCheckpoint) -> Result<(), ReadError> {
+                match self.checkpoints.get(checkpoint.index) {
+                    Some(saved) => self.inner.restore(&**saved),
+                    None => Err(ReadError::InvalidCheckpoint),
+                }
+            }
+            #[inline]
+            fn offset(&mut self) -> AddressDiff<A> {
+                AddressDiff::from_const(A::Diff::from_word_count(
+                    (self.inner.offset() / <$word as FromBytes>::SIZE as u64) as usize
+                ))
+            }
+            #[inline]
+            fn total_offset(&mut self) -> AddressDiff<A> {
+                AddressDiff::from_const(A::Diff::from_word_count(
+                    (self.inner.total_offset() / <$word as FromBytes>::SIZE as u64) as usize
+                ))
+            }
+            #[inline]
+            fn as_slice_reader(&mut self) -> Option<&mut dyn SliceReader> {
+                self.inner.as_slice_reader()
+            }
+        }
+    }
+}
+
+dyn_word_reader_impl!(u8);
+dyn_word_reader_impl!(U16le);
+dyn_word_reader_impl!(U16be);
+dyn_word_reader_impl!(U32le);
+dyn_word_reader_impl!(U32be);
+dyn_word_reader_impl!(U64le);
+dyn_word_reader_impl!(U64be);
+dyn_word_reader_impl!(U24le);
+dyn_word_reader_impl!(U24be);
+dyn_word_reader_impl!(U32pdp);
+
+macro_rules! dyn_word_reader_impl_bi_endian {
+    ($word:ident) => {
+        dyn_word_reader_impl!($word, |word: $word, endianness: Endianness| {
+            match endianness {
+                Endianness::Little => word,
+                Endianness::Big => $word(word.0.swap_bytes()),
+            }
+        });
+    }
+}
+
+dyn_word_reader_impl_bi_endian!(U16bi);
+dyn_word_reader_impl_bi_endian!(U32bi);
+dyn_word_reader_impl_bi_endian!(U64bi);
+
+/// the object-safe parts of `DecodeError`, for `DynDecodeError` to hold.
+trait ErasedDecodeError: std::error::Error + Send + Sync + 'static {
+    fn data_exhausted(&self) -> bool;
+    fn bad_opcode(&self) -> bool;
+    fn bad_operand(&self) -> bool;
+    fn description(&self) -> &'static str;
+    fn read_error(&self) -> Option<&ReadError>;
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<E: DecodeError + std::error::Error> ErasedDecodeError for E {
+    fn data_exhausted(&self) -> bool { DecodeError::data_exhausted(self) }
+    fn bad_opcode(&self) -> bool { DecodeError::bad_opcode(self) }
+    fn bad_operand(&self) -> bool { DecodeError::bad_operand(self) }
+    fn description(&self) -> &'static str { DecodeError::description(self) }
+    fn read_error(&self) -> Option<&ReadError> { DecodeError::read_error(self) }
+    fn as_any(&self) -> &dyn Any { self }
+}
+
+/// a `DecodeError` from an architecture chosen at runtime. the questions `DecodeError` answers can
+/// be asked of a `DynDecodeError` too, and the original error can be recovered with
+/// [`DynDecodeError::downcast_ref`].
+pub struct DynDecodeError {
+    error: Box<dyn ErasedDecodeError>,
+}
+
+impl DynDecodeError {
+    /// as `DecodeError::data_exhausted`.
+    pub fn data_exhausted(&self) -> bool {
+        self.error.data_exhausted()
+    }
+    /// as `DecodeError::bad_opcode`.
+    pub fn bad_opcode(&self) -> bool {
+        self.error.bad_opcode()
+    }
+    /// as `DecodeError::bad_operand`.
+    pub fn bad_operand(&self) -> bool {
+        self.error.bad_operand()
+    }
+    /// as `DecodeError::description`.
+    pub fn description(&self) -> &'static str {
+        ErasedDecodeError::description(&*self.error)
+    }
+    /// as `DecodeError::read_error`.
+    pub fn read_error(&self) -> Option<&ReadError> {
+        self.error.read_error()
+    }
+    /// the original `Arch::DecodeError`, if it is an `E`.
+    pub fn downcast_ref<E: DecodeError>(&self) -> Option<&E> {
+        self.error.as_any().downcast_ref()
+    }
+}
+
+impl fmt::Debug for DynDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.error, f)
+    }
+}
+
+impl fmt::Display for DynDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for DynDecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// an `A::Instruction`, boxed up as a `DynInstruction`.
+struct ErasedInstruction<A: Arch + ?Sized> {
+    inst: A::Instruction,
+}
+
+impl<A: Arch + ?Sized> fmt::Debug for ErasedInstruction<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.inst, f)
+    }
+}
+
+impl<A: Arch + ?Sized> fmt::Display for ErasedInstruction<A> where A::Instruction: fmt::Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.inst, f)
+    }
+}
+
+impl<A: Arch + ?Sized> DynInstruction for ErasedInstruction<A> where A::Instruction: fmt::Display + 'static {
+    fn len(&self) -> usize {
+        A::Address::zero().wrapping_offset(self.inst.len()).to_linear()
+    }
+    fn well_defined(&self) -> bool {
+        self.inst.well_defined()
+    }
+    fn as_any(&self) -> &dyn Any {
+        &self.inst
+    }
+}
+
+/// a [`DynDecoder`] for the architecture `A`, using an `A::Decoder`. the decoder reads words from
+/// a [`DynWordReader`]; bi-endian words are read little-endian unless
+/// [`ArchDecoder::with_endianness`] says otherwise.
+pub struct ArchDecoder<A: Arch + ?Sized> {
+    decoder: A::Decoder,
+    endianness: Endianness,
+}
+
+impl<A: Arch + ?Sized> ArchDecoder<A> {
+    /// a `DynDecoder` using `A`'s default decoder.
+    pub fn new() -> ArchDecoder<A> {
+        ArchDecoder::with_decoder(A::Decoder::default())
+    }
+
+    /// a `DynDecoder` using `decoder`, for architectures whose decoders can be configured.
+    pub fn with_decoder(decoder: A::Decoder) -> ArchDecoder<A> {
+        ArchDecoder { decoder, endianness: Endianness::Little }
+    }
+
+    /// read bi-endian words, such as [`crate::U32bi`], in the byte order `endianness`.
+    pub fn with_endianness(self, endianness: Endianness) -> ArchDecoder<A> {
+        ArchDecoder { endianness, ..self }
+    }
+
+    /// the underlying `A::Decoder`.
+    pub fn decoder(&self) -> &A::Decoder {
+        &self.decoder
+    }
+}
+
+impl<A: Arch + ?Sized> Default for ArchDecoder<A> {
+    fn default() -> ArchDecoder<A> {
+        ArchDecoder::new()
+    }
+}
+
+impl<A: Arch + ?Sized + 'static> DynDecoder for ArchDecoder<A>
+    where A::Instruction: fmt::Display + 'static,
+          for<'a> DynWordReader<'a>: Reader<A::Address, A::Word> {
+    fn decode(&self, words: &mut dyn DynReader) -> Result<Box<dyn DynInstruction>, DynDecodeError> {
+        let mut words = DynWordReader::new(words, self.endianness);
+        match self.decoder.decode(&mut words) {
+            Ok(inst) => Ok(Box::new(ErasedInstruction::<A> { inst })),
+            Err(e) => Err(DynDecodeError { error: Box::new(e) }),
+        }
+    }
+}
@@ -11,13 +11,15 @@ use core::hash::Hash;
 use serde::{Serialize, Deserialize};
 
 mod address;
-pub use address::{Address, AddressBase, AddressDiff, AddressDiffAmount, AddressDisplay};
+pub use address::{Address, AddressBase, AddressDiff, AddressDiffAmount, AddressDisplay, FromWordCount};
 pub use address::{AddressDisplayUsize, AddressDisplayU64, AddressDisplayU32, AddressDisplayU16};
 #[cfg(feature="address-parse")]
 pub use address::AddrParse;
 
 pub mod annotation;
 
+pub mod compat;
+
 mod color;
 pub use color::{Colorize, NoColors, YaxColors};
 
@@ -26,7 +28,38 @@ pub use color::ColorSettings;
 
 pub mod display;
 mod reader;
-pub use reader::{Reader, ReaderBuilder, ReadError, U8Reader, U16le, U16be, U32le, U32be, U64le, U64be};
+pub use reader::{Reader, ReaderBuilder, SliceReader, ReadError, IoError, Checkpoint, U8Reader, U16le, U16be, U32le, U32be, U64le, U64be};
+pub use reader::{U24le, U24be, U32pdp};
+pub use reader::{Endianness, U16bi, U32bi, U64bi};
+pub use reader::{BitOrder, BitReader};
+pub use reader::{Packing, PackedWordReader};
+pub use reader::{MemoryMap, MemoryMapReader, Permissions, Region};
+pub use reader::{MemoryProvider, ProviderReader};
+pub use reader::{RecordingCheckpoint, RecordingReader};
+pub use reader::BoundedReader;
+pub use reader::{HexCheckpoint, HexError, HexReader};
+#[cfg(feature = "std")]
+pub use reader::IoReader;
+#[cfg(feature = "std")]
+pub use reader::{StreamDecode, StreamReader};
+
+mod sweep;
+pub use sweep::{ErrorRecovery, Sweep};
+
+mod control_flow;
+pub use control_flow::{ControlFlow, Effect};
+
+mod operand;
+pub use operand::{OperandInfo, OperandIter, OperandKind, Operands};
+
+#[cfg(feature = "std")]
+pub mod dynamic;
+#[cfg(feature = "std")]
+pub use dynamic::{ArchDecoder, DynCheckpoint, DynDecodeError, DynDecoder, DynInstruction, DynReader, DynWordReader};
+#[cfg(feature = "std")]
+pub mod registry;
+#[cfg(feature = "std")]
+pub use registry::{ArchEntry, ArchRegistry, RegistryError};
 
 /// the minimum set of errors a `yaxpeax-arch` disassembler may produce.
 ///
@@ -51,6 +84,12 @@ pub trait DecodeError: PartialEq + Display + Debug + Send + Sync + 'static {
     fn bad_operand(&self) -> bool;
     /// a human-friendly description of this decode error.
     fn description(&self) -> &'static str;
+    /// the [`ReadError`] this decode error was caused by, if it was caused by a failed read and
+    /// the error kept it. decode errors that can carry a `ReadError` should return it here, so
+    /// callers can report details such as the address that could not be read.
+    fn read_error(&self) -> Option<&ReadError> {
+        None
+    }
 }
 
 /// a minimal enum implementing `DecodeError`. this is intended to be enough for a low effort,
@@ -156,6 +195,36 @@ pub trait Decoder<A: Arch + ?Sized> {
     /// decoding fails. if `decode_into` returns an error, callers may find contradictory and
     /// useless information in `inst`, as well as *stale data* from whatever was passed in.
     fn decode_into<T: Reader<A::Address, A::Word>>(&self, inst: &mut A::Instruction, words: &mut T) -> Result<(), A::DecodeError>;
+
+    /// decode consecutive instructions from `words` into `out`, stopping at the first error or
+    /// once `out` is full. returns the number of instructions decoded, and the error that stopped
+    /// decoding if there was one. running out of input is reported as an error like any other, so
+    /// decoding to the end of a buffer typically ends with the decoder's `data_exhausted` error.
+    ///
+    /// if `n` instructions were decoded, `out[..n]` holds them and `words` is positioned just
+    /// after the last of them - at the start of the instruction that failed to decode, if one did.
+    /// `out[n]` may hold stale or meaningless data, as with `decode_into`. `words`' mark is not
+    /// preserved.
+    ///
+    /// returning to the start of a failed instruction relies on `words` being able to `restore` a
+    /// checkpoint taken at its `mark`, as all readers in this crate can. this panics if it cannot.
+    ///
+    /// the default implementation calls `decode_into` for each instruction. decoders for
+    /// fixed-width instruction sets may be able to do better, and can override this, but must keep
+    /// the guarantees above.
+    fn decode_many<T: Reader<A::Address, A::Word>>(&self, words: &mut T, out: &mut [A::Instruction]) -> (usize, Option<A::DecodeError>) {
+        for (i, inst) in out.iter_mut().enumerate() {
+            // mark first: readers that discard data before their mark, such as `IoReader`, keep
+            // everything from a checkpoint taken at the mark.
+            words.mark();
+            let start = words.checkpoint();
+            if let Err(e) = self.decode_into(inst, words) {
+                words.restore(start).expect("reader can return to a checkpoint taken at its mark");
+                return (i, Some(e));
+            }
+        }
+        (out.len(), None)
+    }
 }
 
 #[cfg(feature = "use-serde")]
@@ -206,6 +275,66 @@ pub trait Arch {
     type Operand;
 }
 
+/// facts about an [`Arch`] that do not depend on any particular instruction, for code that
+/// configures itself from an architecture type alone: listings, loaders, readers.
+///
+/// `NAME`, `ENDIANNESS` and `WORD_BITS` must be provided. the other constants have defaults that
+/// are right for many architectures, but should be overridden where they are not.
+pub trait ArchMetadata: Arch {
+    /// the canonical name of this architecture, such as `"x86_64"` or `"armv7"`.
+    const NAME: &'static str;
+    /// the byte order of instruction words and data.
+    const ENDIANNESS: Endianness;
+    /// the width of a pointer, in bits. defaults to the size of `Arch::Address` in memory.
+    const POINTER_BITS: u32 = (core::mem::size_of::<Self::Address>() * 8) as u32;
+    /// the width of an `Arch::Word`, in bits. this is the width of the word in the instruction
+    /// stream, which is not always its size in memory: 24 for `U24le`, not 32.
+    const WORD_BITS: u32;
+    /// the alignment instructions must have, in units of `Arch::Address`.
+    const INSTRUCTION_ALIGNMENT: u32 = 1;
+
+    /// as [`LengthedInstruction::min_size`] for `Arch::Instruction`.
+    fn min_instruction_size() -> AddressDiff<Self::Address> {
+        Self::Instruction::min_size()
+    }
+
+    /// as [`LengthedInstruction::max_size`] for `Arch::Instruction`.
+    fn max_instruction_size() -> Option<AddressDiff<Self::Address>> {
+        Self::Instruction::max_size()
+    }
+}
+
+/// the [`ArchMetadata`] of some architecture, as values rather than an `Arch` type. instruction
+/// sizes are in units of the architecture's `Address`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct ArchInfo {
+    pub name: &'static str,
+    pub endianness: Endianness,
+    pub pointer_bits: u32,
+    pub word_bits: u32,
+    pub instruction_alignment: u32,
+    pub min_instruction_size: usize,
+    pub max_instruction_size: Option<usize>,
+}
+
+impl ArchInfo {
+    /// the metadata of `A`.
+    pub fn of<A: ArchMetadata + ?Sized>() -> ArchInfo {
+        let units = |size: AddressDiff<A::Address>| {
+            <A::Address as num_traits::Zero>::zero().wrapping_offset(size).to_linear()
+        };
+        ArchInfo {
+            name: A::NAME,
+            endianness: A::ENDIANNESS,
+            pointer_bits: A::POINTER_BITS,
+            word_bits: A::WORD_BITS,
+            instruction_alignment: A::INSTRUCTION_ALIGNMENT,
+            min_instruction_size: units(A::min_instruction_size()),
+            max_instruction_size: A::max_instruction_size().map(units),
+        }
+    }
+}
+
 /// instructions have lengths, and minimum possible sizes for advancing a decoder on error.
 ///
 /// unfortunately, this means calling `x.len()` for some `Arch::Instruction` requires importing
@@ -221,6 +350,13 @@ pub trait LengthedInstruction {
     /// almost always is a number of bytes. implementations should indicate if this is ever not the
     /// case.
     fn min_size() -> Self::Unit;
+    /// the length, in terms of `Unit`, of the longest possible instruction in a given
+    /// architecture, if the architecture limits instruction length at all. x86, for example,
+    /// rejects instructions longer than 15 bytes regardless of how they are encoded.
+    /// [`BoundedReader::for_arch`] uses this to stop decoders from reading past that limit.
+    fn max_size() -> Option<Self::Unit> {
+        None
+    }
 }
 
 pub trait Instruction {
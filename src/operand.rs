@@ -0,0 +1,87 @@
+/// the broad kind of an operand, as reported by [`OperandInfo::kind`].
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum OperandKind {
+    /// a register.
+    Register,
+    /// a constant encoded in the instruction.
+    Immediate,
+    /// a memory access, addressed by registers, constants, or both.
+    Memory,
+    /// an offset from the program counter, such as a relative branch target or a pc-relative
+    /// memory access.
+    PcRelative,
+    /// anything else: condition codes, shift specifiers, register lists, and so on.
+    Other,
+}
+
+/// facts about an operand that do not depend on which architecture it is from, for tools such as
+/// constant searchers and cross-reference builders.
+///
+/// architectures implement this for their `Arch::Operand`.
+pub trait OperandInfo {
+    /// what kind of operand this is.
+    fn kind(&self) -> OperandKind;
+
+    /// the value of this operand as a signed number, if it has one: the value of an
+    /// `Immediate`, or the offset of a `PcRelative` operand. architectures should implement this
+    /// for any operand with a constant value, sign-extending it if the architecture does.
+    fn immediate_i64(&self) -> Option<i64> {
+        None
+    }
+
+    /// the value of this operand as an unsigned number, if it has one. by default this is
+    /// `immediate_i64` reinterpreted as a `u64`; architectures with zero-extended immediates
+    /// should override this to match.
+    fn immediate_u64(&self) -> Option<u64> {
+        self.immediate_i64().map(|value| value as u64)
+    }
+}
+
+/// access to the operands of an instruction.
+///
+/// architectures implement this for their `Arch::Instruction`, with `Operand` being their
+/// `Arch::Operand`. generic code can then ask for it with a bound like
+/// `A::Instruction: Operands<Operand = A::Operand>, A::Operand: OperandInfo`.
+pub trait Operands {
+    type Operand;
+
+    /// the number of operands this instruction has.
+    fn operand_count(&self) -> usize;
+
+    /// the operand at `index`, or `None` if `index` is not less than `operand_count()`.
+    fn operand(&self, index: usize) -> Option<Self::Operand>;
+
+    /// all operands of this instruction, in order.
+    fn operands(&self) -> OperandIter<'_, Self> {
+        OperandIter {
+            inst: self,
+            index: 0,
+        }
+    }
+}
+
+/// an iterator over the operands of an instruction, from [`Operands::operands`].
+pub struct OperandIter<'a, I: Operands + ?Sized> {
+    inst: &'a I,
+    index: usize,
+}
+
+impl<'a, I: Operands + ?Sized> Iterator for OperandIter<'a, I> {
+    type Item = I::Operand;
+
+    fn next(&mut self) -> Option<I::Operand> {
+        if self.index >= self.inst.operand_count() {
+            return None;
+        }
+        let operand = self.inst.operand(self.index);
+        self.index += 1;
+        operand
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.inst.operand_count().saturating_sub(self.index);
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, I: Operands + ?Sized> ExactSizeIterator for OperandIter<'a, I> {}
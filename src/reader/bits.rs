@@ -0,0 +1,168 @@
+use crate::{AddressBase, AddressDiff, FromWordCount, Reader, ReadError};
+use super::Checkpoint;
+
+/// the order in which a [`BitReader`] takes bits out of each byte.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum BitOrder {
+    /// bits are read from the most significant bit of each byte to the least significant bit. for
+    /// a multi-bit field, the first bit read is the field's most significant bit.
+    MsbFirst,
+    /// bits are read from the least significant bit of each byte to the most significant bit. for
+    /// a multi-bit field, the first bit read is the field's least significant bit.
+    LsbFirst,
+}
+
+/// a `Reader` for instruction streams that are not byte-aligned. `BitReader` reads `bool` items,
+/// one per bit, from a `&[u8]`, and every offset it reports is a number of bits. decoders for
+/// bit-granular ISAs can then report `LengthedInstruction::len` in bits, and instructions may
+/// start at any bit offset.
+///
+/// for decoders that know they have a `BitReader`, [`BitReader::read_bits`] reads whole fields of
+/// up to 64 bits at a time.
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    order: BitOrder,
+    /// the bit offset into `data` of the next bit to read.
+    bit: u64,
+    /// the bit offset into `data` of the last `mark`.
+    mark: u64,
+    /// the bit offset into `data` this reader started at.
+    start: u64,
+}
+
+impl<'a> BitReader<'a> {
+    /// create a `BitReader` reading bits from the start of `data` in the provided `order`.
+    pub fn new(data: &'a [u8], order: BitOrder) -> BitReader<'a> {
+        BitReader::at_bit(data, order, 0)
+    }
+
+    /// create a `BitReader` reading bits from `data` in the provided `order`, starting at bit
+    /// `bit`. `offset` and `total_offset` are measured from this starting bit, not the start of
+    /// `data`.
+    pub fn at_bit(data: &'a [u8], order: BitOrder, bit: u64) -> BitReader<'a> {
+        BitReader {
+            data,
+            order,
+            bit,
+            mark: bit,
+            start: bit,
+        }
+    }
+
+    /// the number of bits left to read.
+    pub fn remaining_bits(&self) -> u64 {
+        (self.data.len() as u64 * 8).saturating_sub(self.bit)
+    }
+
+    /// read a `width`-bit field. with `BitOrder::MsbFirst` the first bit read is the most
+    /// significant bit of the returned value, with `BitOrder::LsbFirst` the first bit read is the
+    /// least significant bit. `width` may be at most 64.
+    ///
+    /// if fewer than `width` bits remain, this returns `ReadError::Incomplete` with the number of
+    /// missing bits as its `shortfall`, and does not consume any bits.
+    pub fn read_bits(&mut self, width: u32) -> Result<u64, ReadError> {
+        assert!(width <= 64, "BitReader::read_bits can read at most 64 bits at once");
+        let remaining = self.remaining_bits();
+        if (width as u64) > remaining {
+            return Err(ReadError::Incomplete { read: 0, shortfall: (width as u64 - remaining) as usize });
+        }
+
+        let mut value: u64 = 0;
+        let mut done: u32 = 0;
+        while done < width {
+            let byte = self.data[(self.bit / 8) as usize];
+            let bit_in_byte = (self.bit % 8) as u32;
+            let take = (8 - bit_in_byte).min(width - done);
+            let mask = ((1u16 << take) - 1) as u8;
+            match self.order {
+                BitOrder::MsbFirst => {
+                    let chunk = (byte >> (8 - bit_in_byte - take)) & mask;
+                    // `value << 64` would overflow, so shift in two steps.
+                    value = ((value << (take - 1)) << 1) | chunk as u64;
+                }
+                BitOrder::LsbFirst => {
+                    let chunk = (byte >> bit_in_byte) & mask;
+                    value |= (chunk as u64) << done;
+                }
+            }
+            done += take;
+            self.bit += take as u64;
+        }
+
+        Ok(value)
+    }
+
+    /// read a `width`-bit field as [`BitReader::read_bits`] would, without consuming it.
+    pub fn peek_bits(&mut self, width: u32) -> Result<u64, ReadError> {
+        let bit = self.bit;
+        let value = self.read_bits(width);
+        self.bit = bit;
+        value
+    }
+}
+
+impl<A: AddressBase> Reader<A, bool> for BitReader<'_> where A::Diff: FromWordCount {
+    type Checkpoint = Checkpoint;
+
+    #[inline]
+    fn next(&mut self) -> Result<bool, ReadError> {
+        self.read_bits(1).map(|bit| bit != 0)
+    }
+    #[inline]
+    fn next_n(&mut self, buf: &mut [bool]) -> Result<(), ReadError> {
+        let count = core::cmp::min(self.remaining_bits(), buf.len() as u64) as usize;
+        for bit in buf[..count].iter_mut() {
+            // we've checked that enough bits remain, so this read can't fail.
+            *bit = self.read_bits(1)? != 0;
+        }
+        if count < buf.len() {
+            return Err(ReadError::Incomplete { read: count, shortfall: buf.len() - count });
+        }
+        Ok(())
+    }
+    #[inline]
+    fn peek(&mut self) -> Result<bool, ReadError> {
+        self.peek_bits(1).map(|bit| bit != 0)
+    }
+    #[inline]
+    fn peek_n(&mut self, buf: &mut [bool]) -> Result<(), ReadError> {
+        let bit = self.bit;
+        let res = Reader::<A, bool>::next_n(self, buf);
+        self.bit = bit;
+        res
+    }
+    #[inline]
+    fn mark(&mut self) {
+        self.mark = self.bit;
+    }
+    #[inline]
+    fn rewind_to_mark(&mut self) {
+        self.bit = self.mark;
+    }
+    #[inline]
+    fn checkpoint(&mut self) -> Checkpoint {
+        Checkpoint { position: self.bit, mark: self.mark }
+    }
+    #[inline]
+    fn restore(&mut self, checkpoint: Checkpoint) -> Result<(), ReadError> {
+        let len = self.data.len() as u64 * 8;
+        if checkpoint.position > len || checkpoint.mark > len {
+            return Err(ReadError::InvalidCheckpoint);
+        }
+        self.bit = checkpoint.position;
+        self.mark = checkpoint.mark;
+        Ok(())
+    }
+    #[inline]
+    fn offset(&mut self) -> AddressDiff<A> {
+        AddressDiff::from_const(A::Diff::from_word_count(
+            (self.bit - self.mark) as usize
+        ))
+    }
+    #[inline]
+    fn total_offset(&mut self) -> AddressDiff<A> {
+        AddressDiff::from_const(A::Diff::from_word_count(
+            (self.bit - self.start) as usize
+        ))
+    }
+}
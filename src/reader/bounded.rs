@@ -0,0 +1,114 @@
+use crate::{AddressBase, AddressDiff, Arch, FromWordCount, LengthedInstruction, Reader, ReadError};
+
+/// a `Reader` that forwards to an inner reader, but refuses to read past a maximum instruction
+/// length. once `offset()` would exceed the limit, reads fail with `ReadError::TooLong` and
+/// consume nothing. the limit is measured from the last `mark`, in the same units as `offset()`.
+///
+/// reads that would go past the limit are refused before the inner reader is asked for anything,
+/// assuming each item advances `offset()` by one, as it does for `U8Reader` and the other readers
+/// in this crate that count offsets in words. for readers whose items advance `offset()` by more,
+/// such as a [`PackedWordReader`](crate::PackedWordReader) with several address units per word,
+/// a read that passes that check can still go past the limit. it is then undone, but the inner
+/// reader has already read the data.
+///
+/// architectures with a hard cap on instruction size, such as x86's 15 bytes, can report it from
+/// [`LengthedInstruction::max_size`], and [`BoundedReader::for_arch`] applies it automatically.
+pub struct BoundedReader<R, Address: AddressBase> {
+    inner: R,
+    limit: Option<AddressDiff<Address>>,
+}
+
+impl<R, Address: AddressBase> BoundedReader<R, Address> {
+    /// wrap `inner`, allowing at most `limit` to be read between `mark`s.
+    pub fn new(inner: R, limit: AddressDiff<Address>) -> BoundedReader<R, Address> {
+        BoundedReader {
+            inner,
+            limit: Some(limit),
+        }
+    }
+
+    /// wrap `inner`, limiting reads to the maximum instruction size of `A`. if `A` does not declare
+    /// a maximum instruction size, reads are not limited.
+    pub fn for_arch<A: Arch<Address = Address>>(inner: R) -> BoundedReader<R, Address> {
+        BoundedReader {
+            inner,
+            limit: A::Instruction::max_size(),
+        }
+    }
+
+    /// the most this reader allows to be read between `mark`s, if there is a limit.
+    pub fn limit(&self) -> Option<AddressDiff<Address>> {
+        self.limit
+    }
+
+    /// get a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// discard this `BoundedReader` and return the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// run `read`, a read of `items` items, against the inner reader, unless it would go past the
+    /// limit. if it turns out to have gone past the limit anyway, undo it.
+    fn bounded<Item, T, F: FnOnce(&mut R) -> Result<T, ReadError>>(&mut self, items: usize, read: F) -> Result<T, ReadError>
+        where R: Reader<Address, Item>, Address::Diff: FromWordCount {
+        let limit = match self.limit {
+            Some(limit) => limit,
+            None => { return read(&mut self.inner); }
+        };
+        let needed = self.inner.offset().to_const() + Address::Diff::from_word_count(items);
+        if needed > limit.to_const() {
+            return Err(ReadError::TooLong);
+        }
+        let checkpoint = self.inner.checkpoint();
+        let res = read(&mut self.inner);
+        if self.inner.offset() > limit {
+            self.inner.restore(checkpoint)?;
+            return Err(ReadError::TooLong);
+        }
+        res
+    }
+}
+
+impl<Address: AddressBase, Item, R: Reader<Address, Item>> Reader<Address, Item> for BoundedReader<R, Address>
+    where Address::Diff: FromWordCount {
+    type Checkpoint = R::Checkpoint;
+
+    #[inline]
+    fn next(&mut self) -> Result<Item, ReadError> {
+        self.bounded(1, |inner| inner.next())
+    }
+    /// read `buf`-many items, as [`Reader::next_n`]. if reading them all would go past the limit,
+    /// this consumes nothing and returns `ReadError::TooLong`.
+    #[inline]
+    fn next_n(&mut self, buf: &mut [Item]) -> Result<(), ReadError> {
+        self.bounded(buf.len(), |inner| inner.next_n(buf))
+    }
+    #[inline]
+    fn mark(&mut self) {
+        self.inner.mark()
+    }
+    #[inline]
+    fn rewind_to_mark(&mut self) {
+        self.inner.rewind_to_mark()
+    }
+    #[inline]
+    fn checkpoint(&mut self) -> R::Checkpoint {
+        self.inner.checkpoint()
+    }
+    #[inline]
+    fn restore(&mut self, checkpoint: R::Checkpoint) -> Result<(), ReadError> {
+        self.inner.restore(checkpoint)
+    }
+    #[inline]
+    fn offset(&mut self) -> AddressDiff<Address> {
+        self.inner.offset()
+    }
+    #[inline]
+    fn total_offset(&mut self) -> AddressDiff<Address> {
+        self.inner.total_offset()
+    }
+}
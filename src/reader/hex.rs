@@ -0,0 +1,290 @@
+use core::fmt;
+
+use crate::{AddressBase, AddressDiff, FromWordCount, Reader, ReadError};
+use super::{Endianness, FromBytes};
+use super::{U16bi, U32bi, U64bi, U16le, U16be, U32le, U32be, U64le, U64be, U24le, U24be, U32pdp};
+
+/// an error in the text given to [`HexReader::new`]. columns count characters, starting at 1.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum HexError {
+    /// a character that is not a hex digit, a separator, or part of a `\x` or `0x` prefix.
+    InvalidCharacter { column: usize, character: char },
+    /// a byte is missing its second hex digit, or a `\x` or `0x` prefix is not followed by two hex
+    /// digits. `column` is where the byte started.
+    IncompleteByte { column: usize },
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HexError::InvalidCharacter { column, character } => {
+                write!(f, "invalid character {:?} at column {}", character, column)
+            }
+            HexError::IncompleteByte { column } => {
+                write!(f, "incomplete byte at column {}", column)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HexError {}
+
+/// the column, counting characters from 1, of byte index `pos` in `text`.
+fn column(text: &str, pos: usize) -> usize {
+    text[..pos].chars().count() + 1
+}
+
+fn is_separator(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r' | b',')
+}
+
+fn hex_digit(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// parse the next byte of `text` at or after `pos`, returning the byte and the position just
+/// after it, or `None` if only separators remain.
+fn next_byte(text: &str, mut pos: usize) -> Result<Option<(u8, usize)>, HexError> {
+    let bytes = text.as_bytes();
+    loop {
+        match bytes.get(pos) {
+            None => { return Ok(None); }
+            Some(&b) if is_separator(b) => { pos += 1; }
+            Some(_) => { break; }
+        }
+    }
+
+    let start = pos;
+    let prefixed = matches!((bytes[pos], bytes.get(pos + 1)), (b'\\', Some(b'x')) | (b'0', Some(b'x')) | (b'0', Some(b'X')));
+    if prefixed {
+        pos += 2;
+    }
+
+    match (bytes.get(pos).copied().and_then(hex_digit), bytes.get(pos + 1).copied().and_then(hex_digit)) {
+        (Some(high), Some(low)) => Ok(Some(((high << 4) | low, pos + 2))),
+        (Some(_), None) => Err(bad_digit(text, start, pos + 1)),
+        (None, _) => Err(bad_digit(text, start, pos)),
+    }
+}
+
+/// the error for a byte starting at `start` whose digit at `pos` is not a hex digit: either the
+/// byte was cut short, or there is a stray character in it.
+fn bad_digit(text: &str, start: usize, pos: usize) -> HexError {
+    match text.as_bytes().get(pos) {
+        Some(&b) if !is_separator(b) => {
+            let character = text[pos..].chars().next().expect("pos is at a character boundary");
+            HexError::InvalidCharacter { column: column(text, pos), character }
+        }
+        _ => HexError::IncompleteByte { column: column(text, start) },
+    }
+}
+
+/// a `Reader` of bytes written out as hex text, as they appear in tests, bug reports and command
+/// lines. bytes may be written as pairs of hex digits, either run together (`"4889e5"`) or
+/// separated by whitespace or commas (`"48 89 e5"`, `"48, 89, e5"`), and each byte may have a
+/// `\x` or `0x` prefix (`"\x48\x89\xe5"`, `"0x48 0x89 0xe5"`).
+///
+/// the text is checked when the reader is created, so reads never see a malformed byte. bytes are
+/// then parsed as they are read, without allocating. `HexReader` implements `Reader` for all words
+/// `U8Reader` supports, with offsets counted in words as `U8Reader` does. as with `U8Reader`,
+/// bi-endian words are read in the byte order the reader was created with.
+pub struct HexReader<'a> {
+    text: &'a str,
+    endianness: Endianness,
+    /// number of bytes in `text`.
+    len: u64,
+    /// position in `text` of the next byte to read, and the number of bytes read before it.
+    pos: usize,
+    read: u64,
+    /// `pos` and `read` as of the last `mark`.
+    mark_pos: usize,
+    mark_read: u64,
+}
+
+/// a saved position of a [`HexReader`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct HexCheckpoint {
+    pos: usize,
+    read: u64,
+    mark_pos: usize,
+    mark_read: u64,
+}
+
+impl<'a> HexReader<'a> {
+    /// check that `text` is well-formed hex, and create a reader of the bytes it describes.
+    /// bi-endian words read from this reader are little-endian; use
+    /// [`HexReader::with_endianness`] to pick their byte order.
+    pub fn new(text: &'a str) -> Result<HexReader<'a>, HexError> {
+        HexReader::with_endianness(text, Endianness::Little)
+    }
+
+    /// check that `text` is well-formed hex, and create a reader of the bytes it describes that
+    /// reads bi-endian words, such as [`U32bi`], in the byte order `endianness`.
+    pub fn with_endianness(text: &'a str, endianness: Endianness) -> Result<HexReader<'a>, HexError> {
+        let mut len = 0;
+        let mut pos = 0;
+        while let Some((_, next)) = next_byte(text, pos)? {
+            len += 1;
+            pos = next;
+        }
+        Ok(HexReader {
+            text,
+            endianness,
+            len,
+            pos: 0,
+            read: 0,
+            mark_pos: 0,
+            mark_read: 0,
+        })
+    }
+
+    /// the byte order this reader reads bi-endian words in.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// the total number of bytes in this reader's text.
+    pub fn byte_len(&self) -> u64 {
+        self.len
+    }
+
+    fn peek_word<W: FromBytes>(&self) -> Result<(W, usize), ReadError> {
+        if self.len - self.read < W::SIZE as u64 {
+            return Err(ReadError::Incomplete { read: 0, shortfall: 1 });
+        }
+        let mut bytes = [0u8; 8];
+        let mut pos = self.pos;
+        for byte in bytes[..W::SIZE].iter_mut() {
+            let (b, next) = next_byte(self.text, pos)
+                .expect("text was validated")
+                .expect("enough bytes remain");
+            *byte = b;
+            pos = next;
+        }
+        Ok((W::from_bytes(&bytes[..W::SIZE]), pos))
+    }
+
+    fn read_word<W: FromBytes>(&mut self) -> Result<W, ReadError> {
+        let (word, pos) = self.peek_word()?;
+        self.pos = pos;
+        self.read += W::SIZE as u64;
+        Ok(word)
+    }
+
+    fn read_words<W: FromBytes>(&mut self, buf: &mut [W]) -> usize {
+        let available = (self.len - self.read) / W::SIZE as u64;
+        let count = core::cmp::min(available, buf.len() as u64) as usize;
+        for word in buf[..count].iter_mut() {
+            *word = self.read_word().expect("enough words remain");
+        }
+        count
+    }
+}
+
+// `$in_order` puts a word parsed by `FromBytes` into the reader's byte order; that only changes
+// bi-endian words.
+macro_rules! hexreader_reader_impl {
+    ($word:ident) => {
+        hexreader_reader_impl!($word, |word: $word, _: Endianness| word);
+    };
+    ($word:ident, $in_order:expr) => {
+        impl<A: AddressBase> Reader<A, $word> for HexReader<'_> where A::Diff: FromWordCount {
+            type Checkpoint = HexCheckpoint;
+
+            #[inline]
+            fn next(&mut self) -> Result<$word, ReadError> {
+                self.read_word().map(|word| $in_order(word, self.endianness))
+            }
+            #[inline]
+            fn next_n(&mut self, buf: &mut [$word]) -> Result<(), ReadError> {
+                let count = self.read_words(buf);
+                for word in buf[..count].iter_mut() {
+                    *word = $in_order(*word, self.endianness);
+                }
+                if count < buf.len() {
+                    return Err(ReadError::Incomplete { read: count, shortfall: buf.len() - count });
+                }
+                Ok(())
+            }
+            #[inline]
+            fn peek(&mut self) -> Result<$word, ReadError> {
+                self.peek_word().map(|(word, _)| $in_order(word, self.endianness))
+            }
+            #[inline]
+            fn mark(&mut self) {
+                self.mark_pos = self.pos;
+                self.mark_read = self.read;
+            }
+            #[inline]
+            fn rewind_to_mark(&mut self) {
+                self.pos = self.mark_pos;
+                self.read = self.mark_read;
+            }
+            #[inline]
+            fn checkpoint(&mut self) -> HexCheckpoint {
+                HexCheckpoint {
+                    pos: self.pos,
+                    read: self.read,
+                    mark_pos: self.mark_pos,
+                    mark_read: self.mark_read,
+                }
+            }
+            #[inline]
+            fn restore(&mut self, checkpoint: HexCheckpoint) -> Result<(), ReadError> {
+                if checkpoint.pos > self.text.len() || checkpoint.read > self.len ||
+                    checkpoint.mark_pos > self.text.len() || checkpoint.mark_read > self.len {
+                    return Err(ReadError::InvalidCheckpoint);
+                }
+                self.pos = checkpoint.pos;
+                self.read = checkpoint.read;
+                self.mark_pos = checkpoint.mark_pos;
+                self.mark_read = checkpoint.mark_read;
+                Ok(())
+            }
+            #[inline]
+            fn offset(&mut self) -> AddressDiff<A> {
+                AddressDiff::from_const(A::Diff::from_word_count(
+                    ((self.read - self.mark_read) / <$word as FromBytes>::SIZE as u64) as usize
+                ))
+            }
+            #[inline]
+            fn total_offset(&mut self) -> AddressDiff<A> {
+                AddressDiff::from_const(A::Diff::from_word_count(
+                    (self.read / <$word as FromBytes>::SIZE as u64) as usize
+                ))
+            }
+        }
+    }
+}
+
+hexreader_reader_impl!(u8);
+hexreader_reader_impl!(U16le);
+hexreader_reader_impl!(U16be);
+hexreader_reader_impl!(U32le);
+hexreader_reader_impl!(U32be);
+hexreader_reader_impl!(U64le);
+hexreader_reader_impl!(U64be);
+hexreader_reader_impl!(U24le);
+hexreader_reader_impl!(U24be);
+hexreader_reader_impl!(U32pdp);
+
+macro_rules! hexreader_reader_impl_bi_endian {
+    ($word:ident) => {
+        hexreader_reader_impl!($word, |word: $word, endianness: Endianness| {
+            match endianness {
+                Endianness::Little => word,
+                Endianness::Big => $word(word.0.swap_bytes()),
+            }
+        });
+    }
+}
+
+hexreader_reader_impl_bi_endian!(U16bi);
+hexreader_reader_impl_bi_endian!(U32bi);
+hexreader_reader_impl_bi_endian!(U64bi);
@@ -0,0 +1,340 @@
+use crate::{AddressBase, AddressDiff, FromWordCount, Reader, ReadError};
+use crate::{U16bi, U32bi, U64bi, U16le, U16be, U24le, U24be, U32le, U32be, U32pdp, U64le, U64be};
+use super::{Checkpoint, Endianness, FromBytes, IoError};
+
+use std::boxed::Box;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::Arc;
+use std::vec;
+
+/// the default size of an `IoReader`'s buffer. large enough to amortize `read` calls, small enough
+/// that nobody should notice it.
+const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
+
+/// a `Reader` of words from any [`std::io::Read`]. `IoReader` keeps an internal buffer of bytes
+/// read from its source, so decoding from a file or pipe does not mean one `read` call per word,
+/// and does not mean reading the whole file into memory first either.
+///
+/// bytes from the current `mark` onward are retained in the buffer until the next `mark`; the
+/// buffer grows if an instruction is ever longer than the buffer itself. because of this,
+/// `rewind_to_mark` always succeeds, and checkpoints can be `restore`d as long as they were taken
+/// at or after the current `mark`. restoring an older checkpoint fails with
+/// `ReadError::InvalidCheckpoint`.
+///
+/// when the underlying source reports an error, the `Reader` operation fails with
+/// `ReadError::IOError`. the original [`std::io::Error`] is available from the error's
+/// [`IoError::io_error`].
+///
+/// bi-endian words, such as [`U32bi`], are read as little-endian unless another byte order is
+/// picked with [`IoReader::with_endianness`].
+pub struct IoReader<R> {
+    inner: R,
+    buf: Box<[u8]>,
+    /// number of valid bytes at the start of `buf`.
+    filled: usize,
+    /// index in `buf` of the next byte to be read.
+    pos: usize,
+    /// index in `buf` of the last `mark`.
+    mark: usize,
+    /// offset in `inner`, from where this reader started, of `buf[0]`.
+    buf_offset: u64,
+    endianness: Endianness,
+}
+
+impl<R: Read> IoReader<R> {
+    /// create a new `IoReader` reading from the current position of `inner`, with a default-sized
+    /// buffer.
+    pub fn new(inner: R) -> IoReader<R> {
+        IoReader::with_capacity(DEFAULT_BUFFER_SIZE, inner)
+    }
+
+    /// create a new `IoReader` reading from the current position of `inner`, buffering up to
+    /// `capacity` bytes at a time.
+    pub fn with_capacity(capacity: usize, inner: R) -> IoReader<R> {
+        IoReader {
+            inner,
+            buf: vec![0u8; capacity.max(8)].into_boxed_slice(),
+            filled: 0,
+            pos: 0,
+            mark: 0,
+            buf_offset: 0,
+            endianness: Endianness::Little,
+        }
+    }
+
+    /// read bi-endian words in the byte order `endianness`. words with a fixed byte order, like
+    /// `U32le`, are unaffected.
+    pub fn with_endianness(mut self, endianness: Endianness) -> IoReader<R> {
+        self.endianness = endianness;
+        self
+    }
+
+    /// the byte order this reader reads bi-endian words in.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// get a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// discard this `IoReader` and return the underlying reader. any buffered data is lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// make at least `size` bytes available at `buf[pos..]`, reading from `inner` as needed. if
+    /// `inner` runs out of data first, this returns `ReadError::ExhaustedInput`, and the reader
+    /// is left at the same position.
+    fn fill(&mut self, size: usize) -> Result<(), ReadError> {
+        if self.filled - self.pos >= size {
+            return Ok(());
+        }
+
+        // make room: discard everything before the mark, then grow if that still isn't enough.
+        if self.mark != 0 {
+            self.buf.copy_within(self.mark..self.filled, 0);
+            self.buf_offset += self.mark as u64;
+            self.filled -= self.mark;
+            self.pos -= self.mark;
+            self.mark = 0;
+        }
+        let needed = self.pos + size;
+        if needed > self.buf.len() {
+            let mut new_buf = vec![0u8; needed.max(self.buf.len() * 2)].into_boxed_slice();
+            new_buf[..self.filled].copy_from_slice(&self.buf[..self.filled]);
+            self.buf = new_buf;
+        }
+
+        while self.filled < needed {
+            match self.inner.read(&mut self.buf[self.filled..]) {
+                Ok(0) => {
+                    return Err(ReadError::ExhaustedInput);
+                }
+                Ok(n) => {
+                    self.filled += n;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    return Err(ReadError::IOError(IoError::from(e)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn peek_word<W: FromBytes>(&mut self) -> Result<W, ReadError> {
+        match self.fill(W::SIZE) {
+            Ok(()) => {}
+            // `fill` counts bytes, but this read was for one whole word.
+            Err(ReadError::ExhaustedInput) => { return Err(ReadError::Incomplete { read: 0, shortfall: 1 }); }
+            Err(e) => { return Err(e); }
+        }
+        Ok(W::from_bytes(&self.buf[self.pos..][..W::SIZE]))
+    }
+
+    /// decode as many of `buf` as are available, returning the number of words decoded.
+    fn peek_words<W: FromBytes>(&mut self, buf: &mut [W]) -> Result<usize, ReadError> {
+        match self.fill(buf.len() * W::SIZE) {
+            // on running out of input, all remaining data is in the buffer, so decode what we can.
+            Ok(()) | Err(ReadError::ExhaustedInput) => {}
+            Err(e) => { return Err(e); }
+        }
+        let mut count = 0;
+        for (word, bytes) in buf.iter_mut().zip(self.buf[self.pos..self.filled].chunks_exact(W::SIZE)) {
+            *word = W::from_bytes(bytes);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn read_word<W: FromBytes>(&mut self) -> Result<W, ReadError> {
+        let word = self.peek_word()?;
+        self.pos += W::SIZE;
+        Ok(word)
+    }
+
+    fn read_words<W: FromBytes>(&mut self, buf: &mut [W]) -> Result<usize, ReadError> {
+        let count = self.peek_words(buf)?;
+        self.pos += count * W::SIZE;
+        Ok(count)
+    }
+
+    fn save_checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            position: self.stream_offset(),
+            mark: self.buf_offset + self.mark as u64,
+        }
+    }
+
+    fn restore_checkpoint(&mut self, checkpoint: Checkpoint) -> Result<(), ReadError> {
+        // only the bytes in `buf` can be returned to; anything before them has been discarded.
+        let buffered_end = self.buf_offset + self.filled as u64;
+        let in_buffer = |offset: u64| offset >= self.buf_offset && offset <= buffered_end;
+        if !in_buffer(checkpoint.position) || !in_buffer(checkpoint.mark) {
+            return Err(ReadError::InvalidCheckpoint);
+        }
+        self.pos = (checkpoint.position - self.buf_offset) as usize;
+        self.mark = (checkpoint.mark - self.buf_offset) as usize;
+        Ok(())
+    }
+
+    /// bytes read since the last `mark`.
+    fn mark_offset(&self) -> u64 {
+        (self.pos - self.mark) as u64
+    }
+
+    /// bytes read since this reader was created.
+    fn stream_offset(&self) -> u64 {
+        self.buf_offset + self.pos as u64
+    }
+}
+
+impl<R: Read + Seek> IoReader<R> {
+    /// move this reader to `offset` bytes from where it started reading, and `mark` that position.
+    /// `total_offset` continues to be measured from the position `inner` was at when this reader
+    /// was created.
+    pub fn seek_to(&mut self, offset: u64) -> io::Result<()> {
+        let buffered_end = self.buf_offset + self.filled as u64;
+        if offset >= self.buf_offset && offset <= buffered_end {
+            // no need to touch `inner`, we already have the data.
+            self.pos = (offset - self.buf_offset) as usize;
+            self.mark = self.pos;
+            return Ok(());
+        }
+
+        let distance = offset as i128 - buffered_end as i128;
+        if distance < i64::MIN as i128 || distance > i64::MAX as i128 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "seek offset out of range"));
+        }
+        self.inner.seek(SeekFrom::Current(distance as i64))?;
+        self.buf_offset = offset;
+        self.filled = 0;
+        self.pos = 0;
+        self.mark = 0;
+        Ok(())
+    }
+}
+
+impl From<io::Error> for IoError {
+    fn from(error: io::Error) -> IoError {
+        IoError {
+            description: describe_error(error.kind()),
+            source: Some(Arc::new(error)),
+        }
+    }
+}
+
+fn describe_error(kind: io::ErrorKind) -> &'static str {
+    match kind {
+        io::ErrorKind::NotFound => "entity not found",
+        io::ErrorKind::PermissionDenied => "permission denied",
+        io::ErrorKind::ConnectionReset => "connection reset",
+        io::ErrorKind::ConnectionAborted => "connection aborted",
+        io::ErrorKind::BrokenPipe => "broken pipe",
+        io::ErrorKind::WouldBlock => "operation would block",
+        io::ErrorKind::InvalidInput => "invalid input parameter",
+        io::ErrorKind::InvalidData => "invalid data",
+        io::ErrorKind::TimedOut => "timed out",
+        io::ErrorKind::UnexpectedEof => "unexpected end of file",
+        _ => "i/o error",
+    }
+}
+
+macro_rules! ioreader_reader_impl {
+    ($word:ident) => {
+        ioreader_reader_impl!($word, |word: $word, _: Endianness| word);
+    };
+    ($word:ident, $in_order:expr) => {
+        impl<R: Read, A: AddressBase> Reader<A, $word> for IoReader<R> where A::Diff: FromWordCount {
+            type Checkpoint = Checkpoint;
+
+            #[inline]
+            fn next(&mut self) -> Result<$word, ReadError> {
+                self.read_word().map(|word| $in_order(word, self.endianness))
+            }
+            #[inline]
+            fn next_n(&mut self, buf: &mut [$word]) -> Result<(), ReadError> {
+                let count = self.read_words(buf)?;
+                for word in buf[..count].iter_mut() {
+                    *word = $in_order(*word, self.endianness);
+                }
+                if count < buf.len() {
+                    return Err(ReadError::Incomplete { read: count, shortfall: buf.len() - count });
+                }
+                Ok(())
+            }
+            #[inline]
+            fn peek(&mut self) -> Result<$word, ReadError> {
+                self.peek_word().map(|word| $in_order(word, self.endianness))
+            }
+            #[inline]
+            fn peek_n(&mut self, buf: &mut [$word]) -> Result<(), ReadError> {
+                let count = self.peek_words(buf)?;
+                for word in buf[..count].iter_mut() {
+                    *word = $in_order(*word, self.endianness);
+                }
+                if count < buf.len() {
+                    return Err(ReadError::Incomplete { read: count, shortfall: buf.len() - count });
+                }
+                Ok(())
+            }
+            #[inline]
+            fn mark(&mut self) {
+                self.mark = self.pos;
+            }
+            #[inline]
+            fn rewind_to_mark(&mut self) {
+                self.pos = self.mark;
+            }
+            #[inline]
+            fn checkpoint(&mut self) -> Checkpoint {
+                self.save_checkpoint()
+            }
+            #[inline]
+            fn restore(&mut self, checkpoint: Checkpoint) -> Result<(), ReadError> {
+                self.restore_checkpoint(checkpoint)
+            }
+            #[inline]
+            fn offset(&mut self) -> AddressDiff<A> {
+                AddressDiff::from_const(A::Diff::from_word_count(
+                    (self.mark_offset() / <$word as FromBytes>::SIZE as u64) as usize
+                ))
+            }
+            #[inline]
+            fn total_offset(&mut self) -> AddressDiff<A> {
+                AddressDiff::from_const(A::Diff::from_word_count(
+                    (self.stream_offset() / <$word as FromBytes>::SIZE as u64) as usize
+                ))
+            }
+        }
+    }
+}
+
+ioreader_reader_impl!(u8);
+ioreader_reader_impl!(U16le);
+ioreader_reader_impl!(U16be);
+ioreader_reader_impl!(U32le);
+ioreader_reader_impl!(U32be);
+ioreader_reader_impl!(U64le);
+ioreader_reader_impl!(U64be);
+ioreader_reader_impl!(U24le);
+ioreader_reader_impl!(U24be);
+ioreader_reader_impl!(U32pdp);
+
+macro_rules! ioreader_reader_impl_bi_endian {
+    ($word:ident) => {
+        ioreader_reader_impl!($word, |word: $word, endianness: Endianness| {
+            match endianness {
+                Endianness::Little => word,
+                Endianness::Big => $word(word.0.swap_bytes()),
+            }
+        });
+    }
+}
+
+ioreader_reader_impl_bi_endian!(U16bi);
+ioreader_reader_impl_bi_endian!(U32bi);
+ioreader_reader_impl_bi_endian!(U64bi);
@@ -0,0 +1,284 @@
+use crate::{AddressBase, AddressDiff, FromWordCount, Reader, ReaderBuilder, ReadError};
+use super::{Checkpoint, Endianness, FromBytes};
+use super::{U16bi, U32bi, U64bi, U16le, U16be, U32le, U32be, U64le, U64be, U24le, U24be, U32pdp};
+
+/// access permissions of a [`Region`], as a set of `READ`, `WRITE` and `EXECUTE` flags.
+/// permissions are recorded for callers to consult, such as to only disassemble executable
+/// regions; `MemoryMapReader` reads any mapped byte regardless of its region's permissions.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct Permissions(u8);
+
+impl Permissions {
+    pub const NONE: Permissions = Permissions(0);
+    pub const READ: Permissions = Permissions(1);
+    pub const WRITE: Permissions = Permissions(2);
+    pub const EXECUTE: Permissions = Permissions(4);
+
+    /// `true` if every permission in `other` is also in `self`.
+    pub fn contains(&self, other: Permissions) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl core::ops::BitOr for Permissions {
+    type Output = Permissions;
+
+    fn bitor(self, other: Permissions) -> Permissions {
+        Permissions(self.0 | other.0)
+    }
+}
+
+/// a contiguous run of bytes at a fixed virtual address in a [`MemoryMap`].
+#[derive(Debug, Copy, Clone)]
+pub struct Region<'a> {
+    /// the virtual address of `data[0]`.
+    pub base: u64,
+    pub data: &'a [u8],
+    pub permissions: Permissions,
+}
+
+impl<'a> Region<'a> {
+    /// `true` if `addr` is one of the addresses this region maps.
+    pub fn contains(&self, addr: u64) -> bool {
+        addr >= self.base && addr - self.base < self.data.len() as u64
+    }
+}
+
+/// a sparse address space made of discontiguous [`Region`]s, such as the segments of a firmware
+/// image or the mappings of a process. addresses outside of every region are holes, and reads that
+/// touch a hole fail with `ReadError::Unmapped`.
+///
+/// regions may be listed in any order. if regions overlap, the first region containing an address
+/// is the one that address is read from.
+///
+/// readers of the map read bi-endian words, such as [`U32bi`], as little-endian unless another
+/// byte order is picked with [`MemoryMap::with_endianness`].
+#[derive(Debug, Copy, Clone)]
+pub struct MemoryMap<'a> {
+    regions: &'a [Region<'a>],
+    endianness: Endianness,
+}
+
+impl<'a> MemoryMap<'a> {
+    pub fn new(regions: &'a [Region<'a>]) -> MemoryMap<'a> {
+        MemoryMap { regions, endianness: Endianness::Little }
+    }
+
+    /// read bi-endian words from this map in the byte order `endianness`, such as the byte order
+    /// an ELF header declares. words with a fixed byte order, like `U32le`, are unaffected.
+    pub fn with_endianness(mut self, endianness: Endianness) -> MemoryMap<'a> {
+        self.endianness = endianness;
+        self
+    }
+
+    /// the byte order readers of this map read bi-endian words in.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// the regions this map was created with.
+    pub fn regions(&self) -> &'a [Region<'a>] {
+        self.regions
+    }
+
+    /// the region that `addr` is read from, if `addr` is mapped at all.
+    pub fn region(&self, addr: u64) -> Option<&'a Region<'a>> {
+        self.regions.iter().find(|region| region.contains(addr))
+    }
+
+    /// copy `buf.len()` bytes starting at `addr` into `buf`. the bytes may span several adjacent
+    /// regions. if any byte is unmapped, this returns `ReadError::Unmapped` with the address of the
+    /// first unmapped byte and the numbers of bytes copied and not copied; the bytes before the
+    /// unmapped one are written to `buf` in that case.
+    pub fn read(&self, addr: u64, buf: &mut [u8]) -> Result<(), ReadError> {
+        let mut done = 0;
+        while done < buf.len() {
+            let here = addr.wrapping_add(done as u64);
+            let region = self.region(here).ok_or(ReadError::Unmapped { address: here, read: done, shortfall: buf.len() - done })?;
+            let available = &region.data[(here - region.base) as usize..];
+            let count = core::cmp::min(available.len(), buf.len() - done);
+            buf[done..][..count].copy_from_slice(&available[..count]);
+            done += count;
+        }
+        Ok(())
+    }
+
+    /// create a reader of this map starting at virtual address `addr`.
+    pub fn reader_at(&self, addr: u64) -> MemoryMapReader<'a> {
+        MemoryMapReader {
+            map: *self,
+            start: addr,
+            addr,
+            mark: addr,
+        }
+    }
+}
+
+/// a `Reader` of a [`MemoryMap`] by virtual address. `MemoryMapReader` implements `Reader` for all
+/// words `U8Reader` supports, and words may straddle the boundary between adjacent regions.
+///
+/// as with `U8Reader`, offsets are measured in words, and `total_offset` is measured from the
+/// address the reader was created at. a read that touches a hole fails with
+/// `ReadError::Unmapped` naming the first unmapped address, and consumes nothing. a bulk read that
+/// reaches a hole consumes the whole words before it, as a bulk read that reaches the end of input
+/// would, and reports how many words it read and how many more it needed.
+pub struct MemoryMapReader<'a> {
+    map: MemoryMap<'a>,
+    /// the address this reader was created at.
+    start: u64,
+    /// the address of the next byte to read.
+    addr: u64,
+    /// the address of the last `mark`.
+    mark: u64,
+}
+
+impl<'a> MemoryMapReader<'a> {
+    /// the virtual address of the next byte this reader will read.
+    pub fn address(&self) -> u64 {
+        self.addr
+    }
+
+    /// the byte order this reader reads bi-endian words in, as set on its [`MemoryMap`].
+    pub fn endianness(&self) -> Endianness {
+        self.map.endianness
+    }
+
+    fn peek_word<W: FromBytes>(&self, addr: u64) -> Result<W, ReadError> {
+        let mut bytes = [0u8; 8];
+        let bytes = &mut bytes[..W::SIZE];
+        match self.map.read(addr, bytes) {
+            Ok(()) => Ok(W::from_bytes(bytes)),
+            // `MemoryMap::read` counts bytes, but this read was for one whole word.
+            Err(ReadError::Unmapped { address, .. }) => Err(ReadError::Unmapped { address, read: 0, shortfall: 1 }),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// read words into `buf` until it is full or a word is unmapped.
+    fn peek_words<W: FromBytes>(&self, buf: &mut [W]) -> Result<(), ReadError> {
+        let len = buf.len();
+        let mut addr = self.addr;
+        for (i, word) in buf.iter_mut().enumerate() {
+            *word = self.peek_word(addr).map_err(|e| match e {
+                ReadError::Unmapped { address, .. } => ReadError::Unmapped { address, read: i, shortfall: len - i },
+                e => e,
+            })?;
+            addr = addr.wrapping_add(W::SIZE as u64);
+        }
+        Ok(())
+    }
+}
+
+macro_rules! map_reader_impl {
+    ($word:ident) => {
+        map_reader_impl!($word, |word: $word, _: Endianness| word);
+    };
+    ($word:ident, $in_order:expr) => {
+        impl<A: AddressBase> Reader<A, $word> for MemoryMapReader<'_> where A::Diff: FromWordCount {
+            type Checkpoint = Checkpoint;
+
+            #[inline]
+            fn next(&mut self) -> Result<$word, ReadError> {
+                let word = self.peek_word(self.addr)?;
+                self.addr = self.addr.wrapping_add(<$word as FromBytes>::SIZE as u64);
+                Ok($in_order(word, self.map.endianness))
+            }
+            #[inline]
+            fn next_n(&mut self, buf: &mut [$word]) -> Result<(), ReadError> {
+                let res = self.peek_words(buf);
+                let count = match &res {
+                    Ok(()) => buf.len(),
+                    Err(e) => e.items_read().unwrap_or(0),
+                };
+                for word in buf[..count].iter_mut() {
+                    *word = $in_order(*word, self.map.endianness);
+                }
+                self.addr = self.addr.wrapping_add((count * <$word as FromBytes>::SIZE) as u64);
+                res
+            }
+            #[inline]
+            fn peek(&mut self) -> Result<$word, ReadError> {
+                self.peek_word(self.addr).map(|word| $in_order(word, self.map.endianness))
+            }
+            #[inline]
+            fn peek_n(&mut self, buf: &mut [$word]) -> Result<(), ReadError> {
+                let res = self.peek_words(buf);
+                let count = match &res {
+                    Ok(()) => buf.len(),
+                    Err(e) => e.items_read().unwrap_or(0),
+                };
+                for word in buf[..count].iter_mut() {
+                    *word = $in_order(*word, self.map.endianness);
+                }
+                res
+            }
+            #[inline]
+            fn mark(&mut self) {
+                self.mark = self.addr;
+            }
+            #[inline]
+            fn rewind_to_mark(&mut self) {
+                self.addr = self.mark;
+            }
+            #[inline]
+            fn checkpoint(&mut self) -> Checkpoint {
+                Checkpoint { position: self.addr, mark: self.mark }
+            }
+            #[inline]
+            fn restore(&mut self, checkpoint: Checkpoint) -> Result<(), ReadError> {
+                if checkpoint.position < self.start || checkpoint.mark < self.start {
+                    return Err(ReadError::InvalidCheckpoint);
+                }
+                self.addr = checkpoint.position;
+                self.mark = checkpoint.mark;
+                Ok(())
+            }
+            #[inline]
+            fn offset(&mut self) -> AddressDiff<A> {
+                AddressDiff::from_const(A::Diff::from_word_count(
+                    (self.addr.wrapping_sub(self.mark) / <$word as FromBytes>::SIZE as u64) as usize
+                ))
+            }
+            #[inline]
+            fn total_offset(&mut self) -> AddressDiff<A> {
+                AddressDiff::from_const(A::Diff::from_word_count(
+                    (self.addr.wrapping_sub(self.start) / <$word as FromBytes>::SIZE as u64) as usize
+                ))
+            }
+        }
+
+        impl<'a, A: AddressBase> ReaderBuilder<A, $word> for MemoryMap<'a> where A::Diff: FromWordCount {
+            type Result = MemoryMapReader<'a>;
+
+            fn read_at(data: Self, addr: A) -> Self::Result {
+                data.reader_at(addr.to_linear() as u64)
+            }
+        }
+    }
+}
+
+map_reader_impl!(u8);
+map_reader_impl!(U16le);
+map_reader_impl!(U16be);
+map_reader_impl!(U32le);
+map_reader_impl!(U32be);
+map_reader_impl!(U64le);
+map_reader_impl!(U64be);
+map_reader_impl!(U24le);
+map_reader_impl!(U24be);
+map_reader_impl!(U32pdp);
+
+macro_rules! map_reader_impl_bi_endian {
+    ($word:ident) => {
+        map_reader_impl!($word, |word: $word, endianness: Endianness| {
+            match endianness {
+                Endianness::Little => word,
+                Endianness::Big => $word(word.0.swap_bytes()),
+            }
+        });
+    }
+}
+
+map_reader_impl_bi_endian!(U16bi);
+map_reader_impl_bi_endian!(U32bi);
+map_reader_impl_bi_endian!(U64bi);
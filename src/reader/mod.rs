@@ -0,0 +1,827 @@
+use core::fmt;
+
+use crate::{AddressBase, AddressDiff, FromWordCount, StandardDecodeError, StandardPartialDecoderError};
+
+mod bits;
+pub use self::bits::{BitOrder, BitReader};
+mod packed;
+pub use self::packed::{Packing, PackedWordReader};
+mod map;
+pub use self::map::{MemoryMap, MemoryMapReader, Permissions, Region};
+mod provider;
+pub use self::provider::{MemoryProvider, ProviderReader};
+mod recording;
+pub use self::recording::{RecordingCheckpoint, RecordingReader};
+mod bounded;
+pub use self::bounded::BoundedReader;
+mod hex;
+pub use self::hex::{HexCheckpoint, HexError, HexReader};
+
+#[cfg(feature = "std")]
+mod io;
+#[cfg(feature = "std")]
+pub use self::io::IoReader;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "std")]
+pub use self::stream::{StreamDecode, StreamReader};
+
+impl From<ReadError> for StandardDecodeError {
+    fn from(err: ReadError) -> StandardDecodeError {
+        match err {
+            ReadError::ExhaustedInput |
+            ReadError::Incomplete { .. } |
+            ReadError::IOError(_) |
+            ReadError::InvalidCheckpoint |
+            ReadError::Unmapped { .. } |
+            ReadError::WordTooWide { .. } => StandardDecodeError::ExhaustedInput,
+            // there may be more input, but no valid instruction is this long.
+            ReadError::TooLong => StandardDecodeError::InvalidOpcode,
+        }
+    }
+}
+
+impl From<ReadError> for StandardPartialDecoderError {
+    fn from(err: ReadError) -> StandardPartialDecoderError {
+        match err {
+            ReadError::ExhaustedInput |
+            ReadError::Incomplete { .. } |
+            ReadError::IOError(_) |
+            ReadError::InvalidCheckpoint |
+            ReadError::Unmapped { .. } |
+            ReadError::WordTooWide { .. } => StandardPartialDecoderError::ExhaustedInput,
+            ReadError::TooLong => StandardPartialDecoderError::InvalidOpcode,
+        }
+    }
+}
+
+/// an error from a [`Reader`]. errors carry as much context as the reader has about what went
+/// wrong; see [`ReadError::address`] and [`ReadError::shortfall`], and [`IoError`] for errors
+/// from an underlying source of data.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ReadError {
+    /// the input ran out, and the reader cannot say more than that. the readers in this crate
+    /// report [`ReadError::Incomplete`] instead.
+    ExhaustedInput,
+    /// the input ran out. `next` and `peek` report this with `read: 0, shortfall: 1`; a bulk read
+    /// (`next_n` or `peek_n`) reports how many items it did read. see [`Reader::next_n`] for what
+    /// is consumed in that case.
+    Incomplete {
+        /// how many items were read before the input ran out.
+        read: usize,
+        /// how many more items the read needed.
+        shortfall: usize,
+    },
+    IOError(IoError),
+    /// a checkpoint passed to [`Reader::restore`] does not describe a position this reader can
+    /// return to.
+    InvalidCheckpoint,
+    /// a read touched an address that is not mapped, such as a hole in a [`MemoryMap`]. as with
+    /// [`ReadError::Incomplete`], a bulk read reports how many items it read before the hole.
+    Unmapped {
+        /// the first unmapped address the read would have needed.
+        address: u64,
+        /// how many items were read before `address`.
+        read: usize,
+        /// how many more items the read needed when it reached `address`.
+        shortfall: usize,
+    },
+    /// a read would have gone past the maximum length of an instruction. see [`BoundedReader`].
+    TooLong,
+    /// the reader's words are `width` bits wide, too wide for the type of item they were read as.
+    /// see [`PackedWordReader`].
+    WordTooWide { width: u32 },
+}
+
+impl ReadError {
+    /// the address a read failed at, if the reader knows it.
+    pub fn address(&self) -> Option<u64> {
+        match self {
+            ReadError::Unmapped { address, .. } => Some(*address),
+            _ => None,
+        }
+    }
+
+    /// how many items a read got before it failed, if the reader knows it.
+    pub fn items_read(&self) -> Option<usize> {
+        match self {
+            ReadError::Incomplete { read, .. } |
+            ReadError::Unmapped { read, .. } => Some(*read),
+            _ => None,
+        }
+    }
+
+    /// how many more items a read needed than the reader could provide, if the reader knows it.
+    pub fn shortfall(&self) -> Option<usize> {
+        match self {
+            ReadError::Incomplete { shortfall, .. } |
+            ReadError::Unmapped { shortfall, .. } => Some(*shortfall),
+            _ => None,
+        }
+    }
+
+    /// `true` if the read failed because the input ran out, whether or not the reader could say
+    /// by how much.
+    pub fn is_end_of_input(&self) -> bool {
+        matches!(self, ReadError::ExhaustedInput | ReadError::Incomplete { .. })
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::ExhaustedInput => f.write_str("exhausted input"),
+            ReadError::Incomplete { read, shortfall } => {
+                write!(f, "input ended after {} items ({} more items needed)", read, shortfall)
+            }
+            ReadError::IOError(e) => write!(f, "i/o error: {}", e),
+            ReadError::InvalidCheckpoint => f.write_str("invalid checkpoint"),
+            ReadError::Unmapped { address, read: _, shortfall } => {
+                write!(f, "address {:#x} is unmapped ({} more items needed)", address, shortfall)
+            }
+            ReadError::TooLong => f.write_str("instruction too long"),
+            ReadError::WordTooWide { width } => write!(f, "{}-bit words are too wide for the items being read", width),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadError::IOError(e) => e.io_error().map(|e| e as &(dyn std::error::Error + 'static)),
+            _ => None,
+        }
+    }
+}
+
+/// an error reported by the source a `Reader` reads from. every `IoError` has a short
+/// description; with the `std` feature, errors from a [`std::io::Read`] also keep the original
+/// [`std::io::Error`].
+///
+/// two `IoError`s are equal if their descriptions are equal, and if both or neither have an
+/// `std::io::Error` of the same kind.
+#[derive(Debug, Clone)]
+pub struct IoError {
+    description: &'static str,
+    #[cfg(feature = "std")]
+    source: Option<std::sync::Arc<std::io::Error>>,
+}
+
+impl IoError {
+    /// an error with `description` and no further detail.
+    pub fn new(description: &'static str) -> IoError {
+        IoError {
+            description,
+            #[cfg(feature = "std")]
+            source: None,
+        }
+    }
+
+    /// a short description of the error.
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+
+    /// the `std::io::Error` this error was created from, if there is one.
+    #[cfg(feature = "std")]
+    pub fn io_error(&self) -> Option<&std::io::Error> {
+        self.source.as_deref()
+    }
+}
+
+impl PartialEq for IoError {
+    fn eq(&self, other: &IoError) -> bool {
+        #[cfg(feature = "std")]
+        {
+            let kinds = (self.source.as_ref().map(|e| e.kind()), other.source.as_ref().map(|e| e.kind()));
+            if kinds.0 != kinds.1 {
+                return false;
+            }
+        }
+        self.description == other.description
+    }
+}
+
+impl Eq for IoError {}
+
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.description)
+    }
+}
+
+/// a saved position of one of the `Reader`s provided by `yaxpeax-arch`, along with the `mark` at
+/// the time. `Checkpoint` is the [`Reader::Checkpoint`] of `U8Reader`, `IoReader`, and
+/// `BitReader`, and is only meaningful to the reader that produced it.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Checkpoint {
+    /// the reader's position, in whatever units the reader counts its data in.
+    position: u64,
+    /// the reader's `mark`, in the same units as `position`.
+    mark: u64,
+}
+
+/// a trait defining how `Item`-sized words are read at `Address`-positioned offsets into some
+/// stream of data. for *most* uses, [`crate::U8Reader`] probably is sufficient. when
+/// reading from data sources that aren't `&[u8]`, `Address` isn't a multiple of `u8`, or `Item`
+/// isn't a multiple of 8 bits, `U8Reader` won't be sufficient.
+pub trait Reader<Address: AddressBase, Item> {
+    /// a saved position of this reader. see [`Reader::checkpoint`].
+    type Checkpoint: Copy;
+
+    fn next(&mut self) -> Result<Item, ReadError>;
+    /// read `buf`-many items from this reader in bulk.
+    ///
+    /// if `Reader` cannot read `buf`-many items, it reads as many whole items as it can into the
+    /// start of `buf`, consumes exactly those items, and returns an error whose
+    /// [`ReadError::items_read`] is the number of items read, `n`: usually
+    /// `ReadError::Incomplete { read: n, shortfall: buf.len() - n }`. `n` may be zero. the reader
+    /// is left at the position it would be at after `n` calls to `next()`; a trailing fraction of
+    /// an item is not consumed. errors without an `items_read` consume nothing.
+    fn next_n(&mut self, buf: &mut [Item]) -> Result<(), ReadError>;
+    /// read the next item without consuming it: `offset()` and `total_offset()` are the same
+    /// before and after a `peek`, and the following `next()` returns the same item.
+    ///
+    /// the default implementation reads an item and `restore`s a checkpoint from before the read.
+    /// readers that can look ahead more cheaply should override this.
+    fn peek(&mut self) -> Result<Item, ReadError> {
+        let checkpoint = self.checkpoint();
+        let item = self.next();
+        self.restore(checkpoint)?;
+        item
+    }
+    /// read `buf`-many items without consuming them, as with [`Reader::peek`]. if `Reader` cannot
+    /// read `buf`-many items, it fills the start of `buf` as `next_n` would and returns
+    /// the same error, but still consumes nothing.
+    fn peek_n(&mut self, buf: &mut [Item]) -> Result<(), ReadError> {
+        let checkpoint = self.checkpoint();
+        let res = self.next_n(buf);
+        self.restore(checkpoint)?;
+        res
+    }
+    /// mark the current position as where to measure `offset` against.
+    fn mark(&mut self);
+    /// move this reader back to its last `mark`. items read since then will be read again, and
+    /// `offset()` is `Address::zero()` afterward.
+    fn rewind_to_mark(&mut self);
+    /// save the current position and `mark` of this reader, to return to later with
+    /// [`Reader::restore`]. this is intended for decoders that try one interpretation of an
+    /// instruction and fall back to another if it doesn't work out.
+    fn checkpoint(&mut self) -> Self::Checkpoint;
+    /// return this reader to the position and `mark` saved in `checkpoint`. restoring a checkpoint
+    /// from a different reader is an error, though readers are not required to detect it. readers
+    /// that do not retain all data they have read, such as `IoReader`, may not be able to return to
+    /// older checkpoints and fail with `ReadError::InvalidCheckpoint`.
+    fn restore(&mut self, checkpoint: Self::Checkpoint) -> Result<(), ReadError>;
+    /// the difference, in `Address`, between the current `Reader` position and its last `mark`.
+    /// when created, a `Reader`'s initial position is `mark`ed, so creating a `Reader` and
+    /// immediately calling `offset()` must return `AddressDiff::zero()`.
+    ///
+    /// this is a distance, not a position, so it is comparable with an instruction's
+    /// `LengthedInstruction::len` without conversion.
+    fn offset(&mut self) -> AddressDiff<Address>;
+    /// the difference, in `Address`, between the current `Reader` position and the initial offset
+    /// when constructed.
+    fn total_offset(&mut self) -> AddressDiff<Address>;
+    /// this reader as a [`SliceReader`], if its remaining data is available as a `&[u8]`. decoders
+    /// can use this to take a faster path than reading one item at a time, and fall back to `next`
+    /// when it returns `None`.
+    ///
+    /// the default implementation returns `None`. readers that wrap another reader should not
+    /// forward this if reading directly from the slice would bypass what the wrapper does.
+    fn as_slice_reader(&mut self) -> Option<&mut dyn SliceReader> {
+        None
+    }
+}
+
+/// a reader whose remaining data is a contiguous `&[u8]` in memory, for decoders that want to
+/// inspect upcoming bytes directly - with table lookups, or SIMD scans for prefixes - and then
+/// consume them in one step. see [`Reader::as_slice_reader`].
+pub trait SliceReader {
+    /// the bytes not yet read.
+    fn remaining(&self) -> &[u8];
+    /// consume the next `n` bytes, as if they had been read. if fewer than `n` bytes remain, this
+    /// returns `ReadError::Incomplete` with `read: 0` and the number of missing bytes as its
+    /// `shortfall`, and consumes nothing.
+    ///
+    /// readers of words larger than a byte count `offset` in words, so decoders should advance by
+    /// whole words to keep offsets meaningful.
+    fn advance(&mut self, n: usize) -> Result<(), ReadError>;
+}
+
+/// a trait defining how to build a `Reader<Address, Item>` from some data source (`Self`).
+/// definitions of `ReaderBuilder` are provided for `U8Reader` on `Word` types that `yaxpeax_arch`
+/// provides, at any `Address` whose `Diff` implements [`crate::FromWordCount`] - external decoder
+/// implementations should also provide `ReaderBuilder` impls if they use custom `Reader` types.
+pub trait ReaderBuilder<Address: AddressBase, Item> where Self: Sized {
+    type Result: Reader<Address, Item>;
+
+    /// construct a reader from `data` beginning at `addr` from its beginning.
+    fn read_at(data: Self, addr: Address) -> Self::Result;
+    /// construct a reader from `data` beginning at the start of `data`.
+    fn read_from(data: Self) -> Self::Result {
+        Self::read_at(data, Address::zero())
+    }
+}
+
+/// the byte order of a bi-endian word, such as [`U32bi`], as chosen when its reader is
+/// constructed.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+/// a struct for `Reader` impls that can operate on units of `u8`.
+pub struct U8Reader<'a> {
+    start: *const u8,
+    data: *const u8,
+    end: *const u8,
+    mark: *const u8,
+    endianness: Endianness,
+    _lifetime: core::marker::PhantomData<&'a [u8]>,
+}
+
+impl SliceReader for U8Reader<'_> {
+    fn remaining(&self) -> &[u8] {
+        let len = self.end as usize - self.data as usize;
+        // SAFETY: `data..end` is always a subslice of the slice this reader was created with.
+        unsafe { core::slice::from_raw_parts(self.data, len) }
+    }
+    fn advance(&mut self, n: usize) -> Result<(), ReadError> {
+        let len = self.end as usize - self.data as usize;
+        if n > len {
+            return Err(ReadError::Incomplete { read: 0, shortfall: n - len });
+        }
+        self.data = self.data.wrapping_add(n);
+        Ok(())
+    }
+}
+
+impl<'a> U8Reader<'a> {
+    /// create a `U8Reader` of `data`. bi-endian words read from this reader are little-endian; use
+    /// [`U8Reader::with_endianness`] to pick their byte order.
+    pub fn new(data: &'a [u8]) -> U8Reader<'a> {
+        U8Reader::with_endianness(data, Endianness::Little)
+    }
+
+    /// create a `U8Reader` of `data` that reads bi-endian words, such as [`U32bi`], in the byte
+    /// order `endianness`. words with a fixed byte order, like `U32le`, are unaffected.
+    pub fn with_endianness(data: &'a [u8], endianness: Endianness) -> U8Reader<'a> {
+
+        // WHY: either on <64b systems we panic on `data.len() > isize::MAX`, or we compute end
+        // without `offset` (which would be UB for such huge slices)
+        #[cfg(not(target_pointer_width = "64"))]
+        let end = data.as_ptr().wrapping_add(data.len());
+
+        // SAFETY: the slice was valid, so data + data.len() does not overflow. at the moment,
+        // there aren't 64-bit systems with 63 bits of virtual address space, so it's not possible
+        // to have a slice length larger than 64-bit isize::MAX.
+        #[cfg(target_pointer_width = "64")]
+        let end = unsafe { data.as_ptr().offset(data.len() as isize) };
+
+        U8Reader {
+            start: data.as_ptr(),
+            data: data.as_ptr(),
+            end,
+            mark: data.as_ptr(),
+            endianness,
+            _lifetime: core::marker::PhantomData,
+        }
+    }
+
+    /// the byte order this reader reads bi-endian words in.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+}
+
+/// a word that can be assembled from the bytes it is encoded as. `U8Reader` copies most words
+/// straight out of its slice, but readers that buffer their data (such as `IoReader`), and words
+/// that are not layout-compatible with their encoding (such as `U24le`), use this to turn bytes
+/// into the words they were asked for.
+pub(crate) trait FromBytes: Sized {
+    /// the number of bytes one word is encoded in.
+    const SIZE: usize;
+    /// build a word from `bytes`. callers must provide exactly `Self::SIZE` bytes.
+    fn from_bytes(bytes: &[u8]) -> Self;
+}
+
+impl FromBytes for u8 {
+    const SIZE: usize = 1;
+    fn from_bytes(bytes: &[u8]) -> Self {
+        bytes[0]
+    }
+}
+
+macro_rules! word_wrapper {
+    ($name:ident, $underlying:ident, $from_bytes:ident) => {
+        #[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+        pub struct $name(pub $underlying);
+
+        impl core::fmt::Display for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+
+        impl FromBytes for $name {
+            const SIZE: usize = core::mem::size_of::<$underlying>();
+            fn from_bytes(bytes: &[u8]) -> Self {
+                let mut word = [0u8; core::mem::size_of::<$underlying>()];
+                word.copy_from_slice(bytes);
+                $name($underlying::$from_bytes(word))
+            }
+        }
+    }
+}
+
+word_wrapper!(U16le, u16, from_le_bytes);
+word_wrapper!(U16be, u16, from_be_bytes);
+word_wrapper!(U32le, u32, from_le_bytes);
+word_wrapper!(U32be, u32, from_be_bytes);
+word_wrapper!(U64le, u64, from_le_bytes);
+word_wrapper!(U64be, u64, from_be_bytes);
+
+/// a 24-bit word, stored in three bytes with the least significant byte first. the upper eight
+/// bits of the `u32` are always zero.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+pub struct U24le(pub u32);
+/// a 24-bit word, stored in three bytes with the most significant byte first. the upper eight
+/// bits of the `u32` are always zero.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+pub struct U24be(pub u32);
+/// a 32-bit word stored in the "middle-endian" order of the PDP-11 and VAX: two little-endian
+/// 16-bit halves, with the most significant half first. the value `0x0a0b0c0d` is stored as the
+/// bytes `0b 0a 0d 0c`.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+pub struct U32pdp(pub u32);
+
+impl core::fmt::Display for U24le {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl core::fmt::Display for U24be {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl core::fmt::Display for U32pdp {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromBytes for U24le {
+    const SIZE: usize = 3;
+    fn from_bytes(bytes: &[u8]) -> Self {
+        U24le(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+    }
+}
+
+impl FromBytes for U24be {
+    const SIZE: usize = 3;
+    fn from_bytes(bytes: &[u8]) -> Self {
+        U24be(u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]))
+    }
+}
+
+impl FromBytes for U32pdp {
+    const SIZE: usize = 4;
+    fn from_bytes(bytes: &[u8]) -> Self {
+        U32pdp(u32::from_le_bytes([bytes[2], bytes[3], bytes[0], bytes[1]]))
+    }
+}
+
+macro_rules! bi_endian_word {
+    ($name:ident, $underlying:ident) => {
+        /// a word whose byte order is chosen at runtime: `U8Reader` and `HexReader` read it in the
+        /// [`Endianness`] the reader was constructed with. one decoder over this word handles both byte orders of
+        /// a bi-endian ISA.
+        #[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+        pub struct $name(pub $underlying);
+
+        impl core::fmt::Display for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+
+        // only the reader knows which byte order to read bi-endian words in, so the byte order here
+        // is only a default.
+        impl FromBytes for $name {
+            const SIZE: usize = core::mem::size_of::<$underlying>();
+            fn from_bytes(bytes: &[u8]) -> Self {
+                let mut word = [0u8; core::mem::size_of::<$underlying>()];
+                word.copy_from_slice(bytes);
+                $name($underlying::from_le_bytes(word))
+            }
+        }
+    }
+}
+
+bi_endian_word!(U16bi, u16);
+bi_endian_word!(U32bi, u32);
+bi_endian_word!(U64bi, u64);
+
+// `U8Reader` counts its offsets in words, so it can be read at any address type that can be
+// built from a count of words.
+macro_rules! u8reader_reader_impl {
+    ($word:ident, $word_from_slice:expr, $words_from_slice:expr) => {
+        impl<A: AddressBase> Reader<A, $word> for U8Reader<'_> where A::Diff: FromWordCount {
+            type Checkpoint = Checkpoint;
+
+            #[inline]
+            fn next(&mut self) -> Result<$word, ReadError> {
+                let data_size = self.end as usize - self.data as usize;
+
+                if <$word as FromBytes>::SIZE > data_size {
+                    return Err(ReadError::Incomplete { read: 0, shortfall: 1 });
+                }
+
+                // `word_from_slice` knows that we have bounds-checked that `word`-many bytes are
+                // available.
+                let word = $word_from_slice(self.data, self.endianness);
+                unsafe {
+                    self.data = self.data.offset(<$word as FromBytes>::SIZE as isize);
+                }
+                Ok(word)
+            }
+            #[inline]
+            fn next_n(&mut self, buf: &mut [$word]) -> Result<(), ReadError> {
+                let data_size = self.end as usize - self.data as usize;
+
+                let available = data_size / <$word as FromBytes>::SIZE;
+                let count = core::cmp::min(available, buf.len());
+
+                // `word_from_slice` knows that we have bounds-checked that `word`-many bytes are
+                // available.
+                $words_from_slice(self.data, self.endianness, &mut buf[..count]);
+                unsafe {
+                    self.data = self.data.add(count * <$word as FromBytes>::SIZE);
+                }
+                if count < buf.len() {
+                    return Err(ReadError::Incomplete { read: count, shortfall: buf.len() - count });
+                }
+                Ok(())
+            }
+            #[inline]
+            fn peek(&mut self) -> Result<$word, ReadError> {
+                let data_size = self.end as usize - self.data as usize;
+
+                if <$word as FromBytes>::SIZE > data_size {
+                    return Err(ReadError::Incomplete { read: 0, shortfall: 1 });
+                }
+
+                Ok($word_from_slice(self.data, self.endianness))
+            }
+            #[inline]
+            fn peek_n(&mut self, buf: &mut [$word]) -> Result<(), ReadError> {
+                let data_size = self.end as usize - self.data as usize;
+
+                let available = data_size / <$word as FromBytes>::SIZE;
+                let count = core::cmp::min(available, buf.len());
+
+                $words_from_slice(self.data, self.endianness, &mut buf[..count]);
+                if count < buf.len() {
+                    return Err(ReadError::Incomplete { read: count, shortfall: buf.len() - count });
+                }
+                Ok(())
+            }
+            #[inline]
+            fn mark(&mut self) {
+                self.mark = self.data;
+            }
+            #[inline]
+            fn rewind_to_mark(&mut self) {
+                self.data = self.mark;
+            }
+            #[inline]
+            fn checkpoint(&mut self) -> Checkpoint {
+                Checkpoint {
+                    position: (self.data as usize - self.start as usize) as u64,
+                    mark: (self.mark as usize - self.start as usize) as u64,
+                }
+            }
+            #[inline]
+            fn restore(&mut self, checkpoint: Checkpoint) -> Result<(), ReadError> {
+                let len = (self.end as usize - self.start as usize) as u64;
+                if checkpoint.position > len || checkpoint.mark > len {
+                    return Err(ReadError::InvalidCheckpoint);
+                }
+                // both offsets are within the reader's slice, so `start` plus either offset is in
+                // bounds.
+                self.data = self.start.wrapping_add(checkpoint.position as usize);
+                self.mark = self.start.wrapping_add(checkpoint.mark as usize);
+                Ok(())
+            }
+            #[inline]
+            fn offset(&mut self) -> AddressDiff<A> {
+                AddressDiff::from_const(A::Diff::from_word_count(
+                    (self.data as usize - self.mark as usize) / <$word as FromBytes>::SIZE
+                ))
+            }
+            #[inline]
+            fn total_offset(&mut self) -> AddressDiff<A> {
+                AddressDiff::from_const(A::Diff::from_word_count(
+                    (self.data as usize - self.start as usize) / <$word as FromBytes>::SIZE
+                ))
+            }
+            #[inline]
+            fn as_slice_reader(&mut self) -> Option<&mut dyn SliceReader> {
+                Some(self)
+            }
+        }
+
+        impl<'data, A: AddressBase> ReaderBuilder<A, $word> for &'data [u8] where A::Diff: FromWordCount {
+            type Result = U8Reader<'data>;
+
+            fn read_at(data: Self, addr: A) -> Self::Result {
+                U8Reader::new(&data[addr.to_linear()..])
+            }
+        }
+    }
+}
+u8reader_reader_impl!(u8,
+    |ptr: *const u8, _: Endianness| { unsafe { core::ptr::read(ptr) } },
+    |ptr: *const u8, _: Endianness, buf: &mut [u8]| {
+        unsafe {
+            core::ptr::copy_nonoverlapping(ptr, buf.as_mut_ptr(), buf.len())
+        }
+    }
+);
+
+u8reader_reader_impl!(U16le,
+    |ptr: *const u8, _: Endianness| {
+        let mut word = [0u8; 2];
+        unsafe {
+            core::ptr::copy_nonoverlapping(ptr, word.as_mut_ptr(), word.len());
+        }
+        U16le(u16::from_le_bytes(word))
+    },
+    |ptr: *const u8, _: Endianness, buf: &mut [U16le]| {
+        // `U16le` are layout-identical to u16, so we can just copy into buf
+        unsafe {
+            core::ptr::copy_nonoverlapping(ptr, buf.as_mut_ptr() as *mut u8, buf.len() * core::mem::size_of::<U16le>())
+        }
+    }
+);
+
+u8reader_reader_impl!(U32le,
+    |ptr: *const u8, _: Endianness| {
+        let mut word = [0u8; 4];
+        unsafe {
+            core::ptr::copy_nonoverlapping(ptr, word.as_mut_ptr(), word.len());
+        }
+        U32le(u32::from_le_bytes(word))
+    },
+    |ptr: *const u8, _: Endianness, buf: &mut [U32le]| {
+        // `U32le` are layout-identical to u32, so we can just copy into buf
+        unsafe {
+            core::ptr::copy_nonoverlapping(ptr, buf.as_mut_ptr() as *mut u8, buf.len() * core::mem::size_of::<U32le>())
+        }
+    }
+);
+
+u8reader_reader_impl!(U64le,
+    |ptr: *const u8, _: Endianness| {
+        let mut word = [0u8; 8];
+        unsafe {
+            core::ptr::copy_nonoverlapping(ptr, word.as_mut_ptr(), word.len());
+        }
+        U64le(u64::from_le_bytes(word))
+    },
+    |ptr: *const u8, _: Endianness, buf: &mut [U64le]| {
+        // `U64le` are layout-identical to u64, so we can just copy into buf
+        unsafe {
+            core::ptr::copy_nonoverlapping(ptr, buf.as_mut_ptr() as *mut u8, buf.len() * core::mem::size_of::<U64le>())
+        }
+    }
+);
+
+u8reader_reader_impl!(U16be,
+    |ptr: *const u8, _: Endianness| {
+        let mut word = [0u8; 2];
+        unsafe {
+            core::ptr::copy_nonoverlapping(ptr, word.as_mut_ptr(), word.len());
+        }
+        U16be(u16::from_be_bytes(word))
+    },
+    |ptr: *const u8, _: Endianness, buf: &mut [U16be]| {
+        // `U16be` are layout-identical to u16, so we can just copy into buf
+        unsafe {
+            core::ptr::copy_nonoverlapping(ptr, buf.as_mut_ptr() as *mut u8, buf.len() * core::mem::size_of::<U16be>())
+        }
+
+        // but now we have to bswap all the words
+        for i in 0..buf.len() {
+            buf[i] = U16be(buf[i].0.swap_bytes());
+        }
+    }
+);
+
+u8reader_reader_impl!(U32be,
+    |ptr: *const u8, _: Endianness| {
+        let mut word = [0u8; 4];
+        unsafe {
+            core::ptr::copy_nonoverlapping(ptr, word.as_mut_ptr(), word.len());
+        }
+        U32be(u32::from_be_bytes(word))
+    },
+    |ptr: *const u8, _: Endianness, buf: &mut [U32be]| {
+        // `U32be` are layout-identical to u32, so we can just copy into buf
+        unsafe {
+            core::ptr::copy_nonoverlapping(ptr, buf.as_mut_ptr() as *mut u8, buf.len() * core::mem::size_of::<U32be>())
+        }
+
+        // but now we have to bswap all the words
+        for i in 0..buf.len() {
+            buf[i] = U32be(buf[i].0.swap_bytes());
+        }
+    }
+);
+
+u8reader_reader_impl!(U64be,
+    |ptr: *const u8, _: Endianness| {
+        let mut word = [0u8; 8];
+        unsafe {
+            core::ptr::copy_nonoverlapping(ptr, word.as_mut_ptr(), word.len());
+        }
+        U64be(u64::from_be_bytes(word))
+    },
+    |ptr: *const u8, _: Endianness, buf: &mut [U64be]| {
+        // `U64be` are layout-identical to u64, so we can just copy into buf
+        unsafe {
+            core::ptr::copy_nonoverlapping(ptr, buf.as_mut_ptr() as *mut u8, buf.len() * core::mem::size_of::<U64be>())
+        }
+
+        // but now we have to bswap all the words
+        for i in 0..buf.len() {
+            buf[i] = U64be(buf[i].0.swap_bytes());
+        }
+    }
+);
+
+// `U24le`, `U24be` and `U32pdp` are not layout-compatible with their encodings, so they are decoded
+// word by word. reading all of `buf` out of one slice still avoids repeated bounds checks.
+macro_rules! u8reader_reader_impl_by_word {
+    ($word:ident) => {
+        u8reader_reader_impl!($word,
+            |ptr: *const u8, _: Endianness| {
+                // SAFETY: `U8Reader` has checked that `SIZE` bytes are available at `ptr`.
+                $word::from_bytes(unsafe { core::slice::from_raw_parts(ptr, <$word as FromBytes>::SIZE) })
+            },
+            |ptr: *const u8, _: Endianness, buf: &mut [$word]| {
+                // SAFETY: `U8Reader` has checked that `buf.len()` words are available at `ptr`.
+                let bytes = unsafe { core::slice::from_raw_parts(ptr, buf.len() * <$word as FromBytes>::SIZE) };
+                for (word, bytes) in buf.iter_mut().zip(bytes.chunks_exact(<$word as FromBytes>::SIZE)) {
+                    *word = $word::from_bytes(bytes);
+                }
+            }
+        );
+    }
+}
+
+u8reader_reader_impl_by_word!(U24le);
+u8reader_reader_impl_by_word!(U24be);
+u8reader_reader_impl_by_word!(U32pdp);
+
+macro_rules! u8reader_reader_impl_bi_endian {
+    ($word:ident, $underlying:ident) => {
+        u8reader_reader_impl!($word,
+            |ptr: *const u8, endianness: Endianness| {
+                let mut word = [0u8; core::mem::size_of::<$underlying>()];
+                unsafe {
+                    core::ptr::copy_nonoverlapping(ptr, word.as_mut_ptr(), word.len());
+                }
+                match endianness {
+                    Endianness::Little => $word($underlying::from_le_bytes(word)),
+                    Endianness::Big => $word($underlying::from_be_bytes(word)),
+                }
+            },
+            |ptr: *const u8, endianness: Endianness, buf: &mut [$word]| {
+                // bi-endian words are layout-identical to their underlying integer, so we can just
+                // copy into buf
+                unsafe {
+                    core::ptr::copy_nonoverlapping(ptr, buf.as_mut_ptr() as *mut u8, buf.len() * core::mem::size_of::<$word>())
+                }
+
+                // and then fix up the byte order of each word as it was in memory
+                for word in buf.iter_mut() {
+                    word.0 = match endianness {
+                        Endianness::Little => $underlying::from_le(word.0),
+                        Endianness::Big => $underlying::from_be(word.0),
+                    };
+                }
+            }
+        );
+    }
+}
+
+u8reader_reader_impl_bi_endian!(U16bi, u16);
+u8reader_reader_impl_bi_endian!(U32bi, u32);
+u8reader_reader_impl_bi_endian!(U64bi, u64);
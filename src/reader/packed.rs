@@ -0,0 +1,201 @@
+use crate::{AddressBase, AddressDiff, FromWordCount, Reader, ReadError};
+use super::{BitOrder, BitReader, Checkpoint};
+
+/// how a [`PackedWordReader`]'s words are laid out in bytes.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Packing {
+    /// each word is stored in its own `bytes`-byte unit, least significant byte first. bits above
+    /// the word's width are padding and are discarded. PIC10/12/16 program memory in a hex file
+    /// is `PaddedLe { bytes: 2 }`, PIC24 program memory (with its "phantom" fourth byte) is
+    /// `PaddedLe { bytes: 4 }`.
+    PaddedLe { bytes: u8 },
+    /// each word is stored in its own `bytes`-byte unit, most significant byte first. bits above
+    /// the word's width are padding and are discarded.
+    PaddedBe { bytes: u8 },
+    /// words are packed back to back with no padding, as a stream of bits in the provided order.
+    /// this is the same bit order as a [`BitReader`] uses: with `BitOrder::MsbFirst` the first
+    /// word's most significant bit is the most significant bit of the first byte.
+    Dense(BitOrder),
+}
+
+/// a `Reader` for instruction words whose width is not a multiple of 8 bits, such as the 12- and
+/// 14-bit words of PIC10/12/16, the 24-bit words of PIC24, or the 36-bit words of some older
+/// machines. words are read as `u16`, `u32` or `u64` items, with bits above the word's width
+/// always zero.
+///
+/// the reader can be used as a `Reader` of any of those item types, but items must be at least
+/// `width` bits wide: reading 24-bit words as `u16` fails with `ReadError::WordTooWide`.
+///
+/// offsets are counted in words, multiplied by the number of address units each word occupies
+/// (see [`PackedWordReader::with_address_units`]). for PIC24, where each 24-bit instruction word
+/// is two units of program address, this makes `offset` agree with the architecture's addresses.
+pub struct PackedWordReader<'a> {
+    data: &'a [u8],
+    width: u32,
+    packing: Packing,
+    units_per_word: u64,
+    /// index of the next word to read.
+    word: u64,
+    /// index of the word at the last `mark`.
+    mark: u64,
+}
+
+impl<'a> PackedWordReader<'a> {
+    /// create a reader of `width`-bit words stored in `data` with the provided `packing`. each
+    /// word is one address unit.
+    ///
+    /// panics if `width` is zero or more than 64, or if `packing` is `PaddedLe`/`PaddedBe` with a
+    /// `bytes` of more than 8 or too small to hold a `width`-bit word.
+    pub fn new(data: &'a [u8], width: u32, packing: Packing) -> PackedWordReader<'a> {
+        assert!(width > 0 && width <= 64, "packed words must be between 1 and 64 bits wide");
+        match packing {
+            Packing::PaddedLe { bytes } | Packing::PaddedBe { bytes } => {
+                assert!(bytes <= 8, "padded words can be at most 8 bytes");
+                assert!(width <= bytes as u32 * 8, "padded words must fit in their padded size");
+            }
+            Packing::Dense(_) => {}
+        }
+        PackedWordReader {
+            data,
+            width,
+            packing,
+            units_per_word: 1,
+            word: 0,
+            mark: 0,
+        }
+    }
+
+    /// set the number of address units each word occupies. offsets reported through `Reader` are
+    /// a number of words multiplied by `units`.
+    pub fn with_address_units(mut self, units: u8) -> PackedWordReader<'a> {
+        self.units_per_word = units as u64;
+        self
+    }
+
+    /// the number of whole words in `data`.
+    fn word_count(&self) -> u64 {
+        match self.packing {
+            Packing::PaddedLe { bytes } | Packing::PaddedBe { bytes } => {
+                self.data.len() as u64 / bytes as u64
+            }
+            Packing::Dense(_) => {
+                self.data.len() as u64 * 8 / self.width as u64
+            }
+        }
+    }
+
+    /// check that this reader's words fit in `bits`-bit items.
+    fn check_item_bits(&self, bits: usize) -> Result<(), ReadError> {
+        if self.width as usize > bits {
+            return Err(ReadError::WordTooWide { width: self.width });
+        }
+        Ok(())
+    }
+
+    /// read the word at `index`, which must be less than `word_count()`.
+    fn word_at(&self, index: u64) -> u64 {
+        let mask = u64::MAX >> (64 - self.width);
+        match self.packing {
+            Packing::PaddedLe { bytes } => {
+                let start = (index * bytes as u64) as usize;
+                let mut value = [0u8; 8];
+                value[..bytes as usize].copy_from_slice(&self.data[start..][..bytes as usize]);
+                u64::from_le_bytes(value) & mask
+            }
+            Packing::PaddedBe { bytes } => {
+                let start = (index * bytes as u64) as usize;
+                let mut value = [0u8; 8];
+                value[8 - bytes as usize..].copy_from_slice(&self.data[start..][..bytes as usize]);
+                u64::from_be_bytes(value) & mask
+            }
+            Packing::Dense(order) => {
+                BitReader::at_bit(self.data, order, index * self.width as u64)
+                    .read_bits(self.width)
+                    .expect("word index is in bounds")
+            }
+        }
+    }
+}
+
+macro_rules! packed_reader_impl {
+    ($word:ident) => {
+        impl<A: AddressBase> Reader<A, $word> for PackedWordReader<'_> where A::Diff: FromWordCount {
+            type Checkpoint = Checkpoint;
+
+            #[inline]
+            fn next(&mut self) -> Result<$word, ReadError> {
+                let word = Reader::<A, $word>::peek(self)?;
+                self.word += 1;
+                Ok(word)
+            }
+            #[inline]
+            fn next_n(&mut self, buf: &mut [$word]) -> Result<(), ReadError> {
+                let res = Reader::<A, $word>::peek_n(self, buf);
+                self.word += match &res {
+                    Ok(()) => buf.len() as u64,
+                    Err(e) => e.items_read().unwrap_or(0) as u64,
+                };
+                res
+            }
+            #[inline]
+            fn peek(&mut self) -> Result<$word, ReadError> {
+                self.check_item_bits(core::mem::size_of::<$word>() * 8)?;
+                if self.word >= self.word_count() {
+                    return Err(ReadError::Incomplete { read: 0, shortfall: 1 });
+                }
+                Ok(self.word_at(self.word) as $word)
+            }
+            #[inline]
+            fn peek_n(&mut self, buf: &mut [$word]) -> Result<(), ReadError> {
+                self.check_item_bits(core::mem::size_of::<$word>() * 8)?;
+                let available = self.word_count() - self.word;
+                let count = core::cmp::min(available, buf.len() as u64) as usize;
+                for (i, word) in buf[..count].iter_mut().enumerate() {
+                    *word = self.word_at(self.word + i as u64) as $word;
+                }
+                if count < buf.len() {
+                    return Err(ReadError::Incomplete { read: count, shortfall: buf.len() - count });
+                }
+                Ok(())
+            }
+            #[inline]
+            fn mark(&mut self) {
+                self.mark = self.word;
+            }
+            #[inline]
+            fn rewind_to_mark(&mut self) {
+                self.word = self.mark;
+            }
+            #[inline]
+            fn checkpoint(&mut self) -> Checkpoint {
+                Checkpoint { position: self.word, mark: self.mark }
+            }
+            #[inline]
+            fn restore(&mut self, checkpoint: Checkpoint) -> Result<(), ReadError> {
+                let count = self.word_count();
+                if checkpoint.position > count || checkpoint.mark > count {
+                    return Err(ReadError::InvalidCheckpoint);
+                }
+                self.word = checkpoint.position;
+                self.mark = checkpoint.mark;
+                Ok(())
+            }
+            #[inline]
+            fn offset(&mut self) -> AddressDiff<A> {
+                AddressDiff::from_const(A::Diff::from_word_count(
+                    ((self.word - self.mark) * self.units_per_word) as usize
+                ))
+            }
+            #[inline]
+            fn total_offset(&mut self) -> AddressDiff<A> {
+                AddressDiff::from_const(A::Diff::from_word_count(
+                    (self.word * self.units_per_word) as usize
+                ))
+            }
+        }
+    }
+}
+
+packed_reader_impl!(u16);
+packed_reader_impl!(u32);
+packed_reader_impl!(u64);
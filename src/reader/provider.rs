@@ -0,0 +1,315 @@
+use crate::{AddressBase, AddressDiff, FromWordCount, Reader, ReadError};
+use super::{Checkpoint, Endianness, FromBytes, IoError};
+use super::{U16bi, U32bi, U64bi, U16le, U16be, U32le, U32be, U64le, U64be, U24le, U24be, U32pdp};
+
+/// a source of memory that is read by address rather than held in a slice: a debugger's view of a
+/// live process, an emulator's address space, a JTAG probe, and so on.
+pub trait MemoryProvider {
+    type Error;
+
+    /// read up to `buf.len()` bytes starting at `addr` into the start of `buf`, returning the
+    /// number of bytes read. returning fewer bytes than requested means the bytes after them are
+    /// not mapped; in particular, returning `0` means `addr` itself is not mapped.
+    fn read(&mut self, addr: u64, buf: &mut [u8]) -> Result<usize, Self::Error>;
+}
+
+impl<P: MemoryProvider + ?Sized> MemoryProvider for &mut P {
+    type Error = P::Error;
+
+    fn read(&mut self, addr: u64, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        (**self).read(addr, buf)
+    }
+}
+
+/// a `Reader` of words from a [`MemoryProvider`], by address. `ProviderReader` caches one page of
+/// memory at a time in a buffer the caller provides, so decoding does not mean one provider call
+/// per word, and no allocation is needed. the page size is the size of that buffer, and pages
+/// start at multiples of it, so rewinding within a page is served from the cache.
+///
+/// as with `U8Reader`, offsets are measured in words, and `total_offset` is measured from the
+/// address the reader was created at. reads of addresses the provider does not map fail with
+/// `ReadError::Unmapped`. when the provider itself reports an error, the `Reader` operation fails
+/// with `ReadError::IOError`; the provider's error is kept and can be retrieved with
+/// [`ProviderReader::take_error`].
+///
+/// only one page is cached at a time, so rewinding to the start of an instruction that straddles
+/// two pages reads the first page from the provider again. a cache a few times larger than the
+/// longest instruction makes that rare.
+///
+/// memory behind a provider may change while it is being read. cached bytes are not re-read until
+/// they are evicted by a read of another page, or the cache is dropped with
+/// [`ProviderReader::invalidate`].
+///
+/// bi-endian words, such as [`U32bi`], are read as little-endian unless another byte order is
+/// picked with [`ProviderReader::with_endianness`].
+pub struct ProviderReader<'cache, P: MemoryProvider> {
+    provider: P,
+    cache: &'cache mut [u8],
+    /// the address of `cache[0]`, always a multiple of `cache.len()`.
+    cache_base: u64,
+    /// the range of `cache` that holds valid bytes.
+    cached_start: usize,
+    cached_end: usize,
+    /// the address this reader was created at.
+    start: u64,
+    /// the address of the next byte to read.
+    addr: u64,
+    /// the address of the last `mark`.
+    mark: u64,
+    error: Option<P::Error>,
+    endianness: Endianness,
+}
+
+impl<'cache, P: MemoryProvider> ProviderReader<'cache, P> {
+    /// create a reader of `provider` starting at `addr`, caching pages of `cache.len()` bytes in
+    /// `cache`.
+    ///
+    /// panics if `cache` is empty.
+    pub fn new(provider: P, cache: &'cache mut [u8], addr: u64) -> ProviderReader<'cache, P> {
+        assert!(!cache.is_empty(), "ProviderReader needs a cache of at least one byte");
+        ProviderReader {
+            provider,
+            cache,
+            cache_base: 0,
+            cached_start: 0,
+            cached_end: 0,
+            start: addr,
+            addr,
+            mark: addr,
+            error: None,
+            endianness: Endianness::Little,
+        }
+    }
+
+    /// read bi-endian words in the byte order `endianness`. words with a fixed byte order, like
+    /// `U32le`, are unaffected.
+    pub fn with_endianness(mut self, endianness: Endianness) -> ProviderReader<'cache, P> {
+        self.endianness = endianness;
+        self
+    }
+
+    /// the byte order this reader reads bi-endian words in.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// take the most recent error reported by the provider, if there was one.
+    pub fn take_error(&mut self) -> Option<P::Error> {
+        self.error.take()
+    }
+
+    /// discard all cached bytes. the next read will ask the provider for fresh data.
+    pub fn invalidate(&mut self) {
+        self.cached_start = 0;
+        self.cached_end = 0;
+    }
+
+    /// the address of the next byte this reader will read.
+    pub fn address(&self) -> u64 {
+        self.addr
+    }
+
+    /// get a reference to the underlying provider.
+    pub fn get_ref(&self) -> &P {
+        &self.provider
+    }
+
+    /// discard this `ProviderReader` and return the underlying provider.
+    pub fn into_inner(self) -> P {
+        self.provider
+    }
+
+    /// fill the cache with the page containing `addr`. if the start of the page is not mapped,
+    /// only the part of the page from `addr` on is cached.
+    fn fill(&mut self, addr: u64) -> Result<(), ReadError> {
+        let page_size = self.cache.len() as u64;
+        self.cache_base = addr - addr % page_size;
+        self.invalidate();
+        let offset = (addr - self.cache_base) as usize;
+        let mut end = self.fill_from(0)?;
+        let mut start = 0;
+        if end <= offset {
+            start = offset;
+            end = self.fill_from(offset)?;
+        }
+        self.cached_start = start;
+        self.cached_end = end;
+        Ok(())
+    }
+
+    /// read from the provider into `cache[start..]`, until the end of the page or the first
+    /// unmapped byte, returning the offset just past the last byte read.
+    fn fill_from(&mut self, start: usize) -> Result<usize, ReadError> {
+        let len = self.cache.len();
+        let mut end = start;
+        while end < len {
+            let at = self.cache_base.wrapping_add(end as u64);
+            match self.provider.read(at, &mut self.cache[end..len]) {
+                Ok(0) => { break; }
+                Ok(n) => {
+                    end += core::cmp::min(n, len - end);
+                }
+                Err(e) => {
+                    self.error = Some(e);
+                    return Err(ReadError::IOError(IoError::new("memory provider error")));
+                }
+            }
+        }
+        Ok(end)
+    }
+
+    /// copy `buf.len()` bytes starting at `addr` into `buf`, going to the provider for any bytes
+    /// that are not cached. as with `MemoryMap::read`, the shortfall of an unmapped read is in
+    /// bytes.
+    fn read_bytes(&mut self, addr: u64, buf: &mut [u8]) -> Result<(), ReadError> {
+        let mut done = 0;
+        while done < buf.len() {
+            let here = addr.wrapping_add(done as u64);
+            let cached = here >= self.cache_base.wrapping_add(self.cached_start as u64) &&
+                here - self.cache_base < self.cached_end as u64;
+            if !cached {
+                self.fill(here)?;
+                if here - self.cache_base >= self.cached_end as u64 {
+                    return Err(ReadError::Unmapped { address: here, read: done, shortfall: buf.len() - done });
+                }
+            }
+            let available = &self.cache[(here - self.cache_base) as usize..self.cached_end];
+            let count = core::cmp::min(available.len(), buf.len() - done);
+            buf[done..][..count].copy_from_slice(&available[..count]);
+            done += count;
+        }
+        Ok(())
+    }
+
+    fn peek_word<W: FromBytes>(&mut self, addr: u64) -> Result<W, ReadError> {
+        let mut bytes = [0u8; 8];
+        let bytes = &mut bytes[..W::SIZE];
+        match self.read_bytes(addr, bytes) {
+            Ok(()) => Ok(W::from_bytes(bytes)),
+            // `read_bytes` counts bytes, but this read was for one whole word.
+            Err(ReadError::Unmapped { address, .. }) => Err(ReadError::Unmapped { address, read: 0, shortfall: 1 }),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// read words into `buf` until it is full or a word is unmapped. provider errors are reported
+    /// without saying how many words were read, so bulk reads that hit them consume nothing.
+    fn peek_words<W: FromBytes>(&mut self, buf: &mut [W]) -> Result<(), ReadError> {
+        let len = buf.len();
+        let mut addr = self.addr;
+        for (i, word) in buf.iter_mut().enumerate() {
+            *word = self.peek_word(addr).map_err(|e| match e {
+                ReadError::Unmapped { address, .. } => ReadError::Unmapped { address, read: i, shortfall: len - i },
+                e => e,
+            })?;
+            addr = addr.wrapping_add(W::SIZE as u64);
+        }
+        Ok(())
+    }
+}
+
+macro_rules! provider_reader_impl {
+    ($word:ident) => {
+        provider_reader_impl!($word, |word: $word, _: Endianness| word);
+    };
+    ($word:ident, $in_order:expr) => {
+        impl<P: MemoryProvider, A: AddressBase> Reader<A, $word> for ProviderReader<'_, P> where A::Diff: FromWordCount {
+            type Checkpoint = Checkpoint;
+
+            #[inline]
+            fn next(&mut self) -> Result<$word, ReadError> {
+                let word = self.peek_word(self.addr)?;
+                self.addr = self.addr.wrapping_add(<$word as FromBytes>::SIZE as u64);
+                Ok($in_order(word, self.endianness))
+            }
+            #[inline]
+            fn next_n(&mut self, buf: &mut [$word]) -> Result<(), ReadError> {
+                let res = self.peek_words(buf);
+                let count = match &res {
+                    Ok(()) => buf.len(),
+                    Err(e) => e.items_read().unwrap_or(0),
+                };
+                for word in buf[..count].iter_mut() {
+                    *word = $in_order(*word, self.endianness);
+                }
+                self.addr = self.addr.wrapping_add((count * <$word as FromBytes>::SIZE) as u64);
+                res
+            }
+            #[inline]
+            fn peek(&mut self) -> Result<$word, ReadError> {
+                self.peek_word(self.addr).map(|word| $in_order(word, self.endianness))
+            }
+            #[inline]
+            fn peek_n(&mut self, buf: &mut [$word]) -> Result<(), ReadError> {
+                let res = self.peek_words(buf);
+                let count = match &res {
+                    Ok(()) => buf.len(),
+                    Err(e) => e.items_read().unwrap_or(0),
+                };
+                for word in buf[..count].iter_mut() {
+                    *word = $in_order(*word, self.endianness);
+                }
+                res
+            }
+            #[inline]
+            fn mark(&mut self) {
+                self.mark = self.addr;
+            }
+            #[inline]
+            fn rewind_to_mark(&mut self) {
+                self.addr = self.mark;
+            }
+            #[inline]
+            fn checkpoint(&mut self) -> Checkpoint {
+                Checkpoint { position: self.addr, mark: self.mark }
+            }
+            #[inline]
+            fn restore(&mut self, checkpoint: Checkpoint) -> Result<(), ReadError> {
+                if checkpoint.position < self.start || checkpoint.mark < self.start {
+                    return Err(ReadError::InvalidCheckpoint);
+                }
+                self.addr = checkpoint.position;
+                self.mark = checkpoint.mark;
+                Ok(())
+            }
+            #[inline]
+            fn offset(&mut self) -> AddressDiff<A> {
+                AddressDiff::from_const(A::Diff::from_word_count(
+                    (self.addr.wrapping_sub(self.mark) / <$word as FromBytes>::SIZE as u64) as usize
+                ))
+            }
+            #[inline]
+            fn total_offset(&mut self) -> AddressDiff<A> {
+                AddressDiff::from_const(A::Diff::from_word_count(
+                    (self.addr.wrapping_sub(self.start) / <$word as FromBytes>::SIZE as u64) as usize
+                ))
+            }
+        }
+    }
+}
+
+provider_reader_impl!(u8);
+provider_reader_impl!(U16le);
+provider_reader_impl!(U16be);
+provider_reader_impl!(U32le);
+provider_reader_impl!(U32be);
+provider_reader_impl!(U64le);
+provider_reader_impl!(U64be);
+provider_reader_impl!(U24le);
+provider_reader_impl!(U24be);
+provider_reader_impl!(U32pdp);
+
+macro_rules! provider_reader_impl_bi_endian {
+    ($word:ident) => {
+        provider_reader_impl!($word, |word: $word, endianness: Endianness| {
+            match endianness {
+                Endianness::Little => word,
+                Endianness::Big => $word(word.0.swap_bytes()),
+            }
+        });
+    }
+}
+
+provider_reader_impl_bi_endian!(U16bi);
+provider_reader_impl_bi_endian!(U32bi);
+provider_reader_impl_bi_endian!(U64bi);
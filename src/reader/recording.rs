@@ -0,0 +1,153 @@
+use crate::{AddressBase, AddressDiff, Reader, ReadError};
+
+/// a `Reader` that forwards to an inner reader and records each item consumed since the last
+/// `mark` into a caller-provided buffer. after decoding an instruction, [`RecordingReader::recorded`]
+/// is the instruction's encoding, whatever kind of reader it was read from.
+///
+/// items beyond the buffer's capacity are still read, but not recorded; `truncated` reports when
+/// that has happened. items that are only `peek`ed are not recorded.
+pub struct RecordingReader<'buf, R, Item> {
+    inner: R,
+    buf: &'buf mut [Item],
+    /// number of items consumed since the last `mark`, whether or not they fit in `buf`.
+    consumed: usize,
+    /// number of times this reader has been `mark`ed, to tell whether a checkpoint's recording is
+    /// still in `buf`.
+    marks: u64,
+    /// set if `buf` does not hold every item consumed since the last `mark`.
+    lost: bool,
+}
+
+/// a saved position of a [`RecordingReader`]: the inner reader's checkpoint, and how much had
+/// been recorded at the time.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct RecordingCheckpoint<C> {
+    inner: C,
+    consumed: usize,
+    marks: u64,
+    lost: bool,
+}
+
+impl<'buf, R, Item: Copy> RecordingReader<'buf, R, Item> {
+    /// wrap `inner`, recording up to `buf.len()` items per `mark`. the position `inner` is at is
+    /// where recording starts, as if it were just `mark`ed.
+    pub fn new(inner: R, buf: &'buf mut [Item]) -> RecordingReader<'buf, R, Item> {
+        RecordingReader {
+            inner,
+            buf,
+            consumed: 0,
+            marks: 0,
+            lost: false,
+        }
+    }
+
+    /// the items consumed since the last `mark`, as many as fit in the buffer.
+    pub fn recorded(&self) -> &[Item] {
+        &self.buf[..core::cmp::min(self.consumed, self.buf.len())]
+    }
+
+    /// `true` if some items consumed since the last `mark` are missing from `recorded()`, either
+    /// because the buffer was too small, or because a checkpoint was restored from before the
+    /// last `mark`.
+    pub fn truncated(&self) -> bool {
+        self.lost || self.consumed > self.buf.len()
+    }
+
+    /// get a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// get a mutable reference to the underlying reader. reading from it directly bypasses the
+    /// recording.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// discard this `RecordingReader` and return the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn record(&mut self, items: &[Item]) {
+        if self.consumed < self.buf.len() {
+            let count = core::cmp::min(items.len(), self.buf.len() - self.consumed);
+            self.buf[self.consumed..][..count].copy_from_slice(&items[..count]);
+        }
+        self.consumed += items.len();
+    }
+}
+
+impl<Address: AddressBase, Item: Copy, R: Reader<Address, Item>> Reader<Address, Item> for RecordingReader<'_, R, Item> {
+    type Checkpoint = RecordingCheckpoint<R::Checkpoint>;
+
+    #[inline]
+    fn next(&mut self) -> Result<Item, ReadError> {
+        let item = self.inner.next()?;
+        self.record(&[item]);
+        Ok(item)
+    }
+    #[inline]
+    fn next_n(&mut self, buf: &mut [Item]) -> Result<(), ReadError> {
+        let res = self.inner.next_n(buf);
+        match &res {
+            Ok(()) => self.record(buf),
+            Err(e) => self.record(&buf[..e.items_read().unwrap_or(0)]),
+        }
+        res
+    }
+    #[inline]
+    fn peek(&mut self) -> Result<Item, ReadError> {
+        self.inner.peek()
+    }
+    #[inline]
+    fn peek_n(&mut self, buf: &mut [Item]) -> Result<(), ReadError> {
+        self.inner.peek_n(buf)
+    }
+    #[inline]
+    fn mark(&mut self) {
+        self.inner.mark();
+        self.consumed = 0;
+        self.marks += 1;
+        self.lost = false;
+    }
+    #[inline]
+    fn rewind_to_mark(&mut self) {
+        self.inner.rewind_to_mark();
+        self.consumed = 0;
+        self.lost = false;
+    }
+    #[inline]
+    fn checkpoint(&mut self) -> Self::Checkpoint {
+        RecordingCheckpoint {
+            inner: self.inner.checkpoint(),
+            consumed: self.consumed,
+            marks: self.marks,
+            lost: self.lost,
+        }
+    }
+    #[inline]
+    fn restore(&mut self, checkpoint: Self::Checkpoint) -> Result<(), ReadError> {
+        self.inner.restore(checkpoint.inner)?;
+        if checkpoint.marks == self.marks {
+            // items up to `consumed` were recorded under the same `mark`, and are still in `buf`.
+            self.consumed = checkpoint.consumed;
+            self.lost = checkpoint.lost;
+        } else {
+            // the checkpoint's recording has since been overwritten. what is recorded from here on
+            // belongs to no earlier checkpoint, so start a new generation.
+            self.consumed = 0;
+            self.marks += 1;
+            self.lost = true;
+        }
+        Ok(())
+    }
+    #[inline]
+    fn offset(&mut self) -> AddressDiff<Address> {
+        self.inner.offset()
+    }
+    #[inline]
+    fn total_offset(&mut self) -> AddressDiff<Address> {
+        self.inner.total_offset()
+    }
+}
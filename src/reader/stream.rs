@@ -0,0 +1,263 @@
+use crate::{AddressBase, AddressDiff, Arch, DecodeError, Decoder, FromWordCount, Reader, ReadError};
+use crate::{U16bi, U32bi, U64bi, U16le, U16be, U24le, U24be, U32le, U32be, U32pdp, U64le, U64be};
+use super::{Checkpoint, Endianness, FromBytes};
+
+use std::vec::Vec;
+
+/// the outcome of [`StreamReader::decode`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum StreamDecode<I, E> {
+    /// an instruction was decoded, and the reader has moved past it.
+    Decoded(I),
+    /// the instruction continues past the data pushed so far. the reader is back at the start of
+    /// the instruction; push more data and decode again.
+    NeedMoreInput,
+    /// the instruction could not be decoded. if the stream has been `finish`ed, this includes an
+    /// instruction cut off by the end of the stream.
+    Error(E),
+}
+
+/// a `Reader` of words from data that arrives in pieces, such as packets from a network capture or
+/// reads from a serial port. data is added with [`StreamReader::push`], and
+/// [`StreamReader::decode`] decodes an instruction if enough data has arrived for it, or reports
+/// that more is needed without losing the partially-read instruction.
+///
+/// reading past the data pushed so far fails with `ReadError::Incomplete`, as any reader would
+/// at the end of its input; the reader remembers that it ran out, which is how `decode` tells
+/// "need more input" apart from a bad instruction. once [`StreamReader::finish`] is called, no
+/// more data is expected, and running out is an error like any other.
+///
+/// bytes before the current `mark` are discarded when more data is pushed, so checkpoints can only
+/// be `restore`d if they were taken at or after the current `mark`.
+pub struct StreamReader {
+    buf: Vec<u8>,
+    /// index in `buf` of the next byte to read.
+    pos: usize,
+    /// index in `buf` of the last `mark`.
+    mark: usize,
+    /// number of bytes discarded from the front of the stream; the stream offset of `buf[0]`.
+    discarded: u64,
+    /// set when a read ran past the data pushed so far.
+    starved: bool,
+    finished: bool,
+    endianness: Endianness,
+}
+
+impl StreamReader {
+    /// create a `StreamReader` with no data yet. bi-endian words read from this reader are
+    /// little-endian; use [`StreamReader::with_endianness`] to pick their byte order.
+    pub fn new() -> StreamReader {
+        StreamReader::with_endianness(Endianness::Little)
+    }
+
+    /// create a `StreamReader` with no data yet, that reads bi-endian words, such as [`U32bi`], in
+    /// the byte order `endianness`.
+    pub fn with_endianness(endianness: Endianness) -> StreamReader {
+        StreamReader {
+            buf: Vec::new(),
+            pos: 0,
+            mark: 0,
+            discarded: 0,
+            starved: false,
+            finished: false,
+            endianness,
+        }
+    }
+
+    /// the byte order this reader reads bi-endian words in.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// append `data` to the stream. bytes before the current `mark` are no longer needed and are
+    /// discarded.
+    pub fn push(&mut self, data: &[u8]) {
+        if self.mark != 0 {
+            self.buf.drain(..self.mark);
+            self.discarded += self.mark as u64;
+            self.pos -= self.mark;
+            self.mark = 0;
+        }
+        self.buf.extend_from_slice(data);
+    }
+
+    /// declare that no more data will be pushed. instructions cut off by the end of the stream are
+    /// then reported as errors rather than `StreamDecode::NeedMoreInput`.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// `true` if [`StreamReader::finish`] has been called.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// the number of bytes pushed but not yet read.
+    pub fn buffered(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    /// decode one instruction from the stream with `decoder`.
+    ///
+    /// if the decoder runs out of data before the stream is `finish`ed, this returns
+    /// `StreamDecode::NeedMoreInput` and leaves the reader at the start of the instruction, so the
+    /// same call can be repeated once more data is pushed. the decoder must report running out of
+    /// data as an error whose `data_exhausted()` is true; other errors are returned as they are,
+    /// even if the decoder looked ahead past the data pushed so far on its way to them.
+    pub fn decode<A: Arch + ?Sized>(&mut self, decoder: &A::Decoder) -> StreamDecode<A::Instruction, A::DecodeError>
+        where StreamReader: Reader<A::Address, A::Word, Checkpoint = Checkpoint> {
+        let start = Reader::<A::Address, A::Word>::checkpoint(self);
+        Reader::<A::Address, A::Word>::mark(self);
+        self.starved = false;
+        match decoder.decode(self) {
+            Ok(inst) => StreamDecode::Decoded(inst),
+            Err(e) if e.data_exhausted() && self.starved && !self.finished => {
+                // the reader still holds everything from `start` on, nothing has been pushed since.
+                Reader::<A::Address, A::Word>::restore(self, start).expect("instruction start is still buffered");
+                StreamDecode::NeedMoreInput
+            }
+            Err(e) => StreamDecode::Error(e),
+        }
+    }
+
+    fn peek_word<W: FromBytes>(&mut self) -> Result<W, ReadError> {
+        if self.buffered() < W::SIZE {
+            self.starved = true;
+            return Err(ReadError::Incomplete { read: 0, shortfall: 1 });
+        }
+        Ok(W::from_bytes(&self.buf[self.pos..][..W::SIZE]))
+    }
+
+    fn peek_words<W: FromBytes>(&mut self, buf: &mut [W]) -> usize {
+        let mut count = 0;
+        for (word, bytes) in buf.iter_mut().zip(self.buf[self.pos..].chunks_exact(W::SIZE)) {
+            *word = W::from_bytes(bytes);
+            count += 1;
+        }
+        if count < buf.len() {
+            self.starved = true;
+        }
+        count
+    }
+
+    fn restore_checkpoint(&mut self, checkpoint: Checkpoint) -> Result<(), ReadError> {
+        let end = self.discarded + self.buf.len() as u64;
+        let in_buffer = |offset: u64| offset >= self.discarded && offset <= end;
+        if !in_buffer(checkpoint.position) || !in_buffer(checkpoint.mark) {
+            return Err(ReadError::InvalidCheckpoint);
+        }
+        self.pos = (checkpoint.position - self.discarded) as usize;
+        self.mark = (checkpoint.mark - self.discarded) as usize;
+        Ok(())
+    }
+}
+
+impl Default for StreamReader {
+    fn default() -> StreamReader {
+        StreamReader::new()
+    }
+}
+
+// as with `U8Reader`, offsets are counted in words, so any address type that can be built from a
+// count of words will do.
+macro_rules! streamreader_reader_impl {
+    ($word:ident) => {
+        streamreader_reader_impl!($word, |word: $word, _: Endianness| word);
+    };
+    ($word:ident, $in_order:expr) => {
+        impl<A: AddressBase> Reader<A, $word> for StreamReader where A::Diff: FromWordCount {
+            type Checkpoint = Checkpoint;
+
+            #[inline]
+            fn next(&mut self) -> Result<$word, ReadError> {
+                let word = self.peek_word()?;
+                self.pos += <$word as FromBytes>::SIZE;
+                Ok($in_order(word, self.endianness))
+            }
+            #[inline]
+            fn next_n(&mut self, buf: &mut [$word]) -> Result<(), ReadError> {
+                let count = self.peek_words(buf);
+                self.pos += count * <$word as FromBytes>::SIZE;
+                for word in buf[..count].iter_mut() {
+                    *word = $in_order(*word, self.endianness);
+                }
+                if count < buf.len() {
+                    return Err(ReadError::Incomplete { read: count, shortfall: buf.len() - count });
+                }
+                Ok(())
+            }
+            #[inline]
+            fn peek(&mut self) -> Result<$word, ReadError> {
+                self.peek_word().map(|word| $in_order(word, self.endianness))
+            }
+            #[inline]
+            fn peek_n(&mut self, buf: &mut [$word]) -> Result<(), ReadError> {
+                let count = self.peek_words(buf);
+                for word in buf[..count].iter_mut() {
+                    *word = $in_order(*word, self.endianness);
+                }
+                if count < buf.len() {
+                    return Err(ReadError::Incomplete { read: count, shortfall: buf.len() - count });
+                }
+                Ok(())
+            }
+            #[inline]
+            fn mark(&mut self) {
+                self.mark = self.pos;
+            }
+            #[inline]
+            fn rewind_to_mark(&mut self) {
+                self.pos = self.mark;
+            }
+            #[inline]
+            fn checkpoint(&mut self) -> Checkpoint {
+                Checkpoint {
+                    position: self.discarded + self.pos as u64,
+                    mark: self.discarded + self.mark as u64,
+                }
+            }
+            #[inline]
+            fn restore(&mut self, checkpoint: Checkpoint) -> Result<(), ReadError> {
+                self.restore_checkpoint(checkpoint)
+            }
+            #[inline]
+            fn offset(&mut self) -> AddressDiff<A> {
+                AddressDiff::from_const(A::Diff::from_word_count(
+                    (self.pos - self.mark) / <$word as FromBytes>::SIZE
+                ))
+            }
+            #[inline]
+            fn total_offset(&mut self) -> AddressDiff<A> {
+                AddressDiff::from_const(A::Diff::from_word_count(
+                    ((self.discarded + self.pos as u64) / <$word as FromBytes>::SIZE as u64) as usize
+                ))
+            }
+        }
+    }
+}
+
+streamreader_reader_impl!(u8);
+streamreader_reader_impl!(U16le);
+streamreader_reader_impl!(U16be);
+streamreader_reader_impl!(U32le);
+streamreader_reader_impl!(U32be);
+streamreader_reader_impl!(U64le);
+streamreader_reader_impl!(U64be);
+streamreader_reader_impl!(U24le);
+streamreader_reader_impl!(U24be);
+streamreader_reader_impl!(U32pdp);
+
+macro_rules! streamreader_reader_impl_bi_endian {
+    ($word:ident) => {
+        streamreader_reader_impl!($word, |word: $word, endianness: Endianness| {
+            match endianness {
+                Endianness::Little => word,
+                Endianness::Big => $word(word.0.swap_bytes()),
+            }
+        });
+    }
+}
+
+streamreader_reader_impl_bi_endian!(U16bi);
+streamreader_reader_impl_bi_endian!(U32bi);
+streamreader_reader_impl_bi_endian!(U64bi);
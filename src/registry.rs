@@ -0,0 +1,241 @@
+//! a table of architectures, looked up by name.
+//!
+//! tools that take an architecture name from a user (a command-line flag, a config file) can
+//! register every architecture they support in an [`ArchRegistry`], and look names up there rather
+//! than maintaining their own tables. names are matched ignoring ASCII case, `-` and `_`, so
+//! `"x86_64"`, `"x86-64"` and `"X86_64"` are the same name.
+
+use core::fmt;
+
+use std::boxed::Box;
+use std::vec::Vec;
+
+use crate::{Arch, ArchInfo, ArchMetadata};
+use crate::dynamic::{ArchDecoder, DynDecoder};
+
+/// an architecture in an [`ArchRegistry`]: its names, a description, its [`ArchInfo`] if it is
+/// known, and how to build a decoder for it.
+#[derive(Clone)]
+pub struct ArchEntry {
+    name: &'static str,
+    aliases: &'static [&'static str],
+    description: &'static str,
+    info: Option<ArchInfo>,
+    decoder: fn() -> Box<dyn DynDecoder>,
+}
+
+fn default_decoder<A: Arch + ?Sized + 'static>() -> Box<dyn DynDecoder>
+    where ArchDecoder<A>: DynDecoder {
+    Box::new(ArchDecoder::<A>::new())
+}
+
+impl ArchEntry {
+    /// an entry for `A`, named `name`, that decodes with `A`'s default decoder.
+    pub fn new<A: Arch + ?Sized + 'static>(name: &'static str) -> ArchEntry
+        where ArchDecoder<A>: DynDecoder {
+        ArchEntry::with_decoder(name, default_decoder::<A>)
+    }
+
+    /// an entry for `A`, named `A::NAME` and with `A`'s [`ArchInfo`], that decodes with `A`'s
+    /// default decoder.
+    pub fn for_arch<A: ArchMetadata + ?Sized + 'static>() -> ArchEntry
+        where ArchDecoder<A>: DynDecoder {
+        ArchEntry::new::<A>(A::NAME).with_info(ArchInfo::of::<A>())
+    }
+
+    /// an entry named `name` that decodes with the decoder `decoder` builds. this is for
+    /// architectures that are registered with a decoder other than their default one, such as a
+    /// configured x86 decoder registered as `"x86_64-avx512"`.
+    pub fn with_decoder(name: &'static str, decoder: fn() -> Box<dyn DynDecoder>) -> ArchEntry {
+        ArchEntry {
+            name,
+            aliases: &[],
+            description: "",
+            info: None,
+            decoder,
+        }
+    }
+
+    /// set other names this entry can be found by.
+    pub fn with_aliases(mut self, aliases: &'static [&'static str]) -> ArchEntry {
+        self.aliases = aliases;
+        self
+    }
+
+    /// set a short, human-readable description of this entry.
+    pub fn with_description(mut self, description: &'static str) -> ArchEntry {
+        self.description = description;
+        self
+    }
+
+    /// set the metadata of this entry's architecture.
+    pub fn with_info(mut self, info: ArchInfo) -> ArchEntry {
+        self.info = Some(info);
+        self
+    }
+
+    /// the canonical name of this entry.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// other names this entry can be found by.
+    pub fn aliases(&self) -> &'static [&'static str] {
+        self.aliases
+    }
+
+    /// the description of this entry, or `""` if it has none.
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+
+    /// the metadata of this entry's architecture, if it was provided.
+    pub fn info(&self) -> Option<&ArchInfo> {
+        self.info.as_ref()
+    }
+
+    /// build a decoder for this entry's architecture.
+    pub fn decoder(&self) -> Box<dyn DynDecoder> {
+        (self.decoder)()
+    }
+
+    /// the canonical name followed by all aliases.
+    fn names(&self) -> impl Iterator<Item = &'static str> {
+        core::iter::once(self.name).chain(self.aliases.iter().copied())
+    }
+}
+
+impl fmt::Debug for ArchEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ArchEntry")
+            .field("name", &self.name)
+            .field("aliases", &self.aliases)
+            .field("description", &self.description)
+            .field("info", &self.info)
+            .finish()
+    }
+}
+
+/// an error registering an [`ArchEntry`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum RegistryError {
+    /// `name` is already the name or an alias of `registered`.
+    NameInUse { name: &'static str, registered: &'static str },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegistryError::NameInUse { name, registered } => {
+                write!(f, "the name {:?} is already used by {}", name, registered)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// architectures, looked up by name. see [the module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct ArchRegistry {
+    entries: Vec<ArchEntry>,
+}
+
+/// the bytes of `name` that matter when comparing names: ASCII lowercase, without `-` or `_`.
+fn normalized(name: &str) -> impl Iterator<Item = u8> + '_ {
+    name.bytes().filter(|b| *b != b'-' && *b != b'_').map(|b| b.to_ascii_lowercase())
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    normalized(a).eq(normalized(b))
+}
+
+/// the edit distance between two normalized names.
+fn distance(a: &[u8], b: &[u8]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + if ca == cb { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = core::cmp::min(substitution, core::cmp::min(row[j], row[j + 1]) + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// how well `name` matches `query`, lower is better, or `None` if it does not match at all.
+fn match_rank(query: &str, name: &str) -> Option<usize> {
+    let query: Vec<u8> = normalized(query).collect();
+    let name: Vec<u8> = normalized(name).collect();
+    if query == name {
+        Some(0)
+    } else if query.is_empty() {
+        None
+    } else if name.starts_with(&query) {
+        Some(1)
+    } else if name.windows(query.len()).any(|window| window == &query[..]) {
+        Some(2)
+    } else {
+        // allow about one typo for every four characters.
+        let d = distance(&query, &name);
+        if d <= core::cmp::max(1, query.len() / 4) {
+            Some(2 + d)
+        } else {
+            None
+        }
+    }
+}
+
+impl ArchRegistry {
+    /// an empty registry.
+    pub fn new() -> ArchRegistry {
+        ArchRegistry::default()
+    }
+
+    /// add `entry` to the registry. fails if any of its names is already used by an entry in the
+    /// registry, or used twice by `entry` itself.
+    pub fn register(&mut self, entry: ArchEntry) -> Result<(), RegistryError> {
+        for (i, name) in entry.names().enumerate() {
+            if let Some(registered) = self.get(name) {
+                return Err(RegistryError::NameInUse { name, registered: registered.name });
+            }
+            if entry.names().take(i).any(|earlier| same_name(earlier, name)) {
+                return Err(RegistryError::NameInUse { name, registered: entry.name });
+            }
+        }
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// the entry with the name or alias `name`, if there is one.
+    pub fn get(&self, name: &str) -> Option<&ArchEntry> {
+        self.entries.iter().find(|entry| entry.names().any(|n| same_name(n, name)))
+    }
+
+    /// a decoder for the architecture with the name or alias `name`, if there is one.
+    pub fn decoder(&self, name: &str) -> Option<Box<dyn DynDecoder>> {
+        self.get(name).map(ArchEntry::decoder)
+    }
+
+    /// all entries, in the order they were registered.
+    pub fn entries(&self) -> &[ArchEntry] {
+        &self.entries
+    }
+
+    /// entries whose names are close to `query`, best matches first: an exact match, then names
+    /// starting with `query`, then names containing it, then names a typo or two away from it.
+    /// this is for suggestions when [`ArchRegistry::get`] finds nothing, such as "did you mean
+    /// `armv7`?".
+    pub fn search(&self, query: &str) -> Vec<&ArchEntry> {
+        let mut matches: Vec<(usize, usize, &ArchEntry)> = self.entries.iter()
+            .enumerate()
+            .filter_map(|(i, entry)| {
+                entry.names().filter_map(|name| match_rank(query, name)).min().map(|rank| (rank, i, entry))
+            })
+            .collect();
+        matches.sort_by_key(|&(rank, i, _)| (rank, i));
+        matches.into_iter().map(|(_, _, entry)| entry).collect()
+    }
+}
@@ -0,0 +1,153 @@
+use core::fmt;
+
+use crate::{AddressBase, AddressDiff, Arch, Decoder, LengthedInstruction, Reader};
+
+/// what a [`Sweep`] does after an instruction fails to decode.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum ErrorRecovery<Addr: AddressBase> {
+    /// end the sweep after reporting the error.
+    Stop,
+    /// skip `LengthedInstruction::min_size()` from the start of the failed instruction, and
+    /// continue decoding from there. this is what a new `Sweep` does.
+    SkipMinSize,
+    /// skip this much from the start of the failed instruction, and continue decoding from there.
+    Skip(AddressDiff<Addr>),
+}
+
+impl<Addr: AddressBase> fmt::Debug for ErrorRecovery<Addr> where AddressDiff<Addr>: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorRecovery::Stop => f.write_str("Stop"),
+            ErrorRecovery::SkipMinSize => f.write_str("SkipMinSize"),
+            ErrorRecovery::Skip(amount) => f.debug_tuple("Skip").field(amount).finish(),
+        }
+    }
+}
+
+/// one step of a [`Sweep`]: an instruction's address, whether it decoded, and how far the sweep
+/// moved past it.
+type Step<A, T> = (<A as Arch>::Address, Result<T, <A as Arch>::DecodeError>, AddressDiff<<A as Arch>::Address>);
+
+/// a linear sweep over a `Reader`: decode an instruction, move past it, and repeat until the
+/// reader is out of data.
+///
+/// as an `Iterator`, `Sweep` yields `(address, result, length)` for each instruction, where
+/// `length` is how far the sweep moved past it. for instructions that decode, that is
+/// `LengthedInstruction::len`; for instructions that do not, it is the amount skipped according to
+/// the sweep's [`ErrorRecovery`]. [`Sweep::next_into`] does the same, but decodes into an
+/// instruction the caller provides rather than a new one each time.
+///
+/// lengths are also how far the sweep moves in `reader`, so they must be measured in the units of
+/// `Reader::offset` - as they are for the byte-oriented architectures this is usually used with.
+pub struct Sweep<'a, A: Arch + ?Sized, R> {
+    decoder: &'a A::Decoder,
+    reader: &'a mut R,
+    address: A::Address,
+    recovery: ErrorRecovery<A::Address>,
+    done: bool,
+}
+
+impl<'a, A: Arch + ?Sized, R: Reader<A::Address, A::Word>> Sweep<'a, A, R> {
+    /// sweep over `reader` with `decoder`, where the first instruction is at `address`.
+    pub fn new(decoder: &'a A::Decoder, reader: &'a mut R, address: A::Address) -> Sweep<'a, A, R> {
+        Sweep {
+            decoder,
+            reader,
+            address,
+            recovery: ErrorRecovery::SkipMinSize,
+            done: false,
+        }
+    }
+
+    /// set what the sweep does after an instruction fails to decode.
+    pub fn with_recovery(mut self, recovery: ErrorRecovery<A::Address>) -> Sweep<'a, A, R> {
+        self.recovery = recovery;
+        self
+    }
+
+    /// the address of the next instruction the sweep will decode.
+    pub fn address(&self) -> A::Address {
+        self.address
+    }
+
+    /// decode the next instruction into `inst`, returning its address, whether it decoded, and
+    /// how far the sweep moved past it. returns `None` once the reader is out of data, or after an
+    /// error if the sweep was set to `ErrorRecovery::Stop`. other read errors are reported through
+    /// the decoder's error, and the sweep ends if it cannot skip past them.
+    ///
+    /// as with `Decoder::decode_into`, `inst` may hold stale or meaningless data if decoding
+    /// failed.
+    pub fn next_into(&mut self, inst: &mut A::Instruction) -> Option<Step<A, ()>> {
+        if self.done {
+            return None;
+        }
+
+        // mark first: readers that discard data before their mark, such as `IoReader`, keep
+        // everything from a checkpoint taken at the mark, and readers that measure from the mark,
+        // such as `BoundedReader`, measure from the start of this instruction.
+        self.reader.mark();
+        // only running out of input ends the sweep quietly. other read errors, like a hole in a
+        // `MemoryMap`, are the decoder's to report.
+        if let Err(e) = self.reader.peek() {
+            if e.is_end_of_input() {
+                self.done = true;
+                return None;
+            }
+        }
+
+        let address = self.address;
+        let start = self.reader.checkpoint();
+        let (res, length) = match self.decoder.decode_into(inst, self.reader) {
+            Ok(()) => {
+                let length = inst.len();
+                (Ok(()), length)
+            }
+            Err(e) => {
+                let skip = match self.recovery {
+                    ErrorRecovery::Stop => {
+                        self.done = true;
+                        return Some((address, Err(e), AddressDiff::zero()));
+                    }
+                    ErrorRecovery::SkipMinSize => A::Instruction::min_size(),
+                    ErrorRecovery::Skip(amount) => amount,
+                };
+                // the decoder may have read any amount before failing; skip from where the
+                // instruction started, not from there.
+                if self.reader.restore(start).is_err() {
+                    self.done = true;
+                    return Some((address, Err(e), AddressDiff::zero()));
+                }
+                let length = self.skip(skip);
+                if length == AddressDiff::zero() {
+                    // nothing could be skipped, so the sweep would not make progress.
+                    self.done = true;
+                }
+                (Err(e), length)
+            }
+        };
+        self.address = address.wrapping_offset(length);
+        Some((address, res, length))
+    }
+
+    /// read words until `amount` has been read or the reader runs out, returning how much was
+    /// read.
+    fn skip(&mut self, amount: AddressDiff<A::Address>) -> AddressDiff<A::Address> {
+        self.reader.mark();
+        while self.reader.offset() < amount {
+            if self.reader.next().is_err() {
+                break;
+            }
+        }
+        self.reader.offset()
+    }
+}
+
+impl<'a, A: Arch + ?Sized, R: Reader<A::Address, A::Word>> Iterator for Sweep<'a, A, R> {
+    type Item = Step<A, A::Instruction>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut inst = A::Instruction::default();
+        self.next_into(&mut inst)
+            .map(|(address, res, length)| (address, res.map(|()| inst), length))
+    }
+}
@@ -2,6 +2,150 @@ use yaxpeax_arch::AddressBase;
 
 mod reader;
 
+/// a made-up ISA for tests of decoding machinery: each instruction is a one-byte length prefix,
+/// followed by that many bytes. lengths over 3 are invalid, so no instruction is longer than 4
+/// bytes.
+mod length_prefixed {
+    use yaxpeax_arch::{Arch, AddressDiff, Decoder, LengthedInstruction, Instruction, Reader, StandardDecodeError};
+
+    pub struct TestIsa {}
+    #[derive(Debug, Default, PartialEq, Eq)]
+    pub struct TestInst { pub length: u8 }
+    impl Arch for TestIsa {
+        type Word = u8;
+        type Address = u64;
+        type Instruction = TestInst;
+        type Decoder = TestIsaDecoder;
+        type DecodeError = StandardDecodeError;
+        type Operand = ();
+    }
+
+    impl Instruction for TestInst {
+        fn well_defined(&self) -> bool { true }
+    }
+
+    impl LengthedInstruction for TestInst {
+        type Unit = AddressDiff<u64>;
+        fn len(&self) -> Self::Unit { AddressDiff::from_const(self.length as u64) }
+        fn min_size() -> Self::Unit { AddressDiff::from_const(1) }
+        fn max_size() -> Option<Self::Unit> { Some(AddressDiff::from_const(4)) }
+    }
+
+    pub struct TestIsaDecoder {
+        /// the longest length prefix to accept. raising this leaves the check to the reader.
+        pub max_len: u8,
+        /// peek at the next four bytes before decoding, as decoders that look for patterns across
+        /// several bytes might.
+        pub lookahead: bool,
+    }
+
+    impl Default for TestIsaDecoder {
+        fn default() -> Self {
+            TestIsaDecoder { max_len: 3, lookahead: false }
+        }
+    }
+
+    impl Decoder<TestIsa> for TestIsaDecoder {
+        fn decode_into<T: Reader<u64, u8>>(&self, inst: &mut TestInst, words: &mut T) -> Result<(), StandardDecodeError> {
+            words.mark();
+            if self.lookahead {
+                // what the lookahead finds doesn't matter, or whether it finds anything at all.
+                let _ = words.peek_n(&mut [0; 4]);
+            }
+            let len = words.next()?;
+            if len > self.max_len {
+                return Err(StandardDecodeError::InvalidOpcode);
+            }
+            for _ in 0..len {
+                words.next()?;
+            }
+            inst.length = words.offset().to_const() as u8;
+            Ok(())
+        }
+    }
+}
+
+/// made-up ISAs for tests of the architecture-agnostic interfaces, with instructions that
+/// `Display` as their opcode. each `ByteIsa` instruction is one byte, and each `HalfIsa`
+/// instruction is one little-endian 16-bit word, where an opcode of 0 is not well-defined.
+mod opcode_isas {
+    use core::fmt;
+    use yaxpeax_arch::{Arch, AddressDiff, ArchMetadata, Decoder, Endianness, LengthedInstruction, Instruction, Reader, StandardDecodeError, U16le};
+
+    pub struct ByteIsa {}
+    #[derive(Debug, Default, PartialEq, Eq)]
+    pub struct ByteInst { pub opcode: u8 }
+    impl Arch for ByteIsa {
+        type Word = u8;
+        type Address = u64;
+        type Instruction = ByteInst;
+        type Decoder = ByteIsaDecoder;
+        type DecodeError = StandardDecodeError;
+        type Operand = ();
+    }
+    impl Instruction for ByteInst {
+        fn well_defined(&self) -> bool { true }
+    }
+    impl LengthedInstruction for ByteInst {
+        type Unit = AddressDiff<u64>;
+        fn len(&self) -> Self::Unit { AddressDiff::from_const(1) }
+        fn min_size() -> Self::Unit { AddressDiff::from_const(1) }
+    }
+    impl fmt::Display for ByteInst {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "byte {:#x}", self.opcode)
+        }
+    }
+    #[derive(Default)]
+    pub struct ByteIsaDecoder {}
+    impl Decoder<ByteIsa> for ByteIsaDecoder {
+        fn decode_into<T: Reader<u64, u8>>(&self, inst: &mut ByteInst, words: &mut T) -> Result<(), StandardDecodeError> {
+            inst.opcode = words.next()?;
+            Ok(())
+        }
+    }
+
+    pub struct HalfIsa {}
+    #[derive(Debug, Default, PartialEq, Eq)]
+    pub struct HalfInst { pub opcode: u16 }
+    impl Arch for HalfIsa {
+        type Word = U16le;
+        type Address = u32;
+        type Instruction = HalfInst;
+        type Decoder = HalfIsaDecoder;
+        type DecodeError = StandardDecodeError;
+        type Operand = ();
+    }
+    impl ArchMetadata for HalfIsa {
+        const NAME: &'static str = "half";
+        const ENDIANNESS: Endianness = Endianness::Little;
+        const WORD_BITS: u32 = 16;
+        const INSTRUCTION_ALIGNMENT: u32 = 2;
+    }
+    impl Instruction for HalfInst {
+        fn well_defined(&self) -> bool { self.opcode != 0 }
+    }
+    impl LengthedInstruction for HalfInst {
+        type Unit = AddressDiff<u32>;
+        fn len(&self) -> Self::Unit { AddressDiff::from_const(2) }
+        fn min_size() -> Self::Unit { AddressDiff::from_const(2) }
+        fn max_size() -> Option<Self::Unit> { Some(AddressDiff::from_const(2)) }
+    }
+    impl fmt::Display for HalfInst {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "half {:#x}", self.opcode)
+        }
+    }
+    #[derive(Default)]
+    pub struct HalfIsaDecoder {}
+    impl Decoder<HalfIsa> for HalfIsaDecoder {
+        fn decode_into<T: Reader<u32, U16le>>(&self, inst: &mut HalfInst, words: &mut T) -> Result<(), StandardDecodeError> {
+            inst.opcode = words.next()?.0;
+            Ok(())
+        }
+    }
+}
+
 #[test]
 fn test_u16() {
     for l in 0..100 {
@@ -76,3 +220,460 @@ fn error_can_bail() {
 
     assert_eq!(exercise_eq(), Err(Error::TestDecode(StandardDecodeError::ExhaustedInput)));
 }
+
+#[test]
+#[allow(deprecated)]
+fn decoder_written_against_0_2_compiles() {
+    use yaxpeax_arch::{AddressDiff, Decoder, LengthedInstruction, StandardDecodeError, U8Reader};
+    // the only change a 0.2 decoder needs: importing `Reader` from `compat`.
+    use yaxpeax_arch::compat::Reader;
+    use length_prefixed::{TestIsa, TestInst};
+
+    struct CompatDecoder {}
+
+    impl Decoder<TestIsa> for CompatDecoder {
+        fn decode_into<T: Reader<u64, u8>>(&self, inst: &mut TestInst, words: &mut T) -> Result<(), StandardDecodeError> {
+            words.mark();
+            let len = words.next()?;
+            for _ in 0..len {
+                words.next()?;
+            }
+            inst.length = words.offset() as u8;
+            Ok(())
+        }
+    }
+
+    let mut reader = U8Reader::new(&[2, 0xaa, 0xbb, 0]);
+    let inst = CompatDecoder {}.decode(&mut reader).unwrap();
+    assert_eq!(inst.len(), AddressDiff::from_const(3));
+    assert_eq!(yaxpeax_arch::Reader::<u64, u8>::total_offset(&mut reader), AddressDiff::from_const(3));
+}
+
+#[test]
+fn bounded_reader_applies_arch_max_size() {
+    use yaxpeax_arch::{AddressDiff, BoundedReader, Decoder, LengthedInstruction, Reader, StandardDecodeError, U8Reader};
+    use length_prefixed::{TestIsa, TestIsaDecoder};
+
+    // leave it to the reader to stop instructions longer than `max_size`.
+    let decoder = TestIsaDecoder { max_len: u8::MAX, ..TestIsaDecoder::default() };
+    let data = [3, 0xaa, 0xbb, 0xcc, 4, 0xaa, 0xbb, 0xcc, 0xdd];
+    let mut reader = BoundedReader::for_arch::<TestIsa>(U8Reader::new(&data));
+    assert_eq!(reader.limit(), Some(AddressDiff::from_const(4)));
+    assert_eq!(decoder.decode(&mut reader).unwrap().len(), AddressDiff::from_const(4));
+    assert_eq!(decoder.decode(&mut reader).unwrap_err(), StandardDecodeError::InvalidOpcode);
+    // the byte that would have been too many is not consumed.
+    assert_eq!(Reader::<u64, u8>::total_offset(&mut reader), AddressDiff::from_const(8));
+}
+
+#[test]
+fn decode_errors_can_surface_read_errors() {
+    use core::fmt;
+    use yaxpeax_arch::{Arch, AddressDiff, DecodeError, Decoder, Instruction, LengthedInstruction};
+    use yaxpeax_arch::{MemoryMap, Permissions, ReadError, Reader, Region, U8Reader, U16le};
+
+    struct WordIsa {}
+    #[derive(Debug, Default, PartialEq, Eq)]
+    struct WordInst { operands: u16 }
+    impl Arch for WordIsa {
+        type Word = U16le;
+        type Address = u64;
+        type Instruction = WordInst;
+        type Decoder = WordIsaDecoder;
+        type DecodeError = Error;
+        type Operand = ();
+    }
+
+    impl Instruction for WordInst {
+        fn well_defined(&self) -> bool { true }
+    }
+
+    impl LengthedInstruction for WordInst {
+        type Unit = AddressDiff<u64>;
+        fn len(&self) -> Self::Unit { AddressDiff::from_const(1 + self.operands as u64) }
+        fn min_size() -> Self::Unit { AddressDiff::from_const(1) }
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Error { Read(ReadError), BadOpcode }
+
+    impl From<ReadError> for Error {
+        fn from(e: ReadError) -> Error { Error::Read(e) }
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(self.description())
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    impl DecodeError for Error {
+        fn data_exhausted(&self) -> bool { matches!(self, Error::Read(_)) }
+        fn bad_opcode(&self) -> bool { *self == Error::BadOpcode }
+        fn bad_operand(&self) -> bool { false }
+        fn description(&self) -> &'static str {
+            match self {
+                Error::Read(_) => "read error",
+                Error::BadOpcode => "bad opcode",
+            }
+        }
+        fn read_error(&self) -> Option<&ReadError> {
+            match self {
+                Error::Read(e) => Some(e),
+                _ => None,
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct WordIsaDecoder {}
+
+    impl Decoder<WordIsa> for WordIsaDecoder {
+        fn decode_into<T: Reader<u64, U16le>>(&self, inst: &mut WordInst, words: &mut T) -> Result<(), Error> {
+            // an operand count, followed by up to three operand words read in bulk.
+            let U16le(operands) = words.next()?;
+            if operands > 3 {
+                return Err(Error::BadOpcode);
+            }
+            words.next_n(&mut [U16le(0); 3][..operands as usize])?;
+            inst.operands = operands;
+            Ok(())
+        }
+    }
+
+    let decoder = WordIsaDecoder::default();
+
+    // the second operand is in a hole in the map.
+    let regions = [Region { base: 0x1000, data: &[0x02, 0x00, 0xaa, 0xbb], permissions: Permissions::READ }];
+    let mut reader = MemoryMap::new(&regions).reader_at(0x1000);
+    let err = decoder.decode(&mut reader).unwrap_err();
+    assert_eq!(err.read_error(), Some(&ReadError::Unmapped { address: 0x1004, read: 1, shortfall: 1 }));
+    assert_eq!(err.read_error().and_then(|e| e.address()), Some(0x1004));
+
+    // neither operand is there, and half a word is not read at all.
+    let err = decoder.decode(&mut U8Reader::new(&[0x02, 0x00, 0xaa])).unwrap_err();
+    assert_eq!(err.read_error(), Some(&ReadError::Incomplete { read: 0, shortfall: 2 }));
+    assert!(err.read_error().map(|e| e.is_end_of_input()).unwrap_or(false));
+
+    assert_eq!(decoder.decode(&mut U8Reader::new(&[0x04, 0x00])).unwrap_err().read_error(), None);
+}
+
+#[test]
+fn stream_reader_waits_for_more_input() {
+    use yaxpeax_arch::{AddressDiff, Reader, StandardDecodeError, StreamDecode, StreamReader};
+    use length_prefixed::{TestIsa, TestInst, TestIsaDecoder};
+
+    let decoder = TestIsaDecoder::default();
+    let mut reader = StreamReader::new();
+    reader.push(&[2, 0xaa, 0xbb, 3, 0xaa]);
+    assert_eq!(reader.decode::<TestIsa>(&decoder), StreamDecode::Decoded(TestInst { length: 3 }));
+    assert_eq!(reader.decode::<TestIsa>(&decoder), StreamDecode::NeedMoreInput);
+    // the partial instruction is still there to be decoded again.
+    assert_eq!(reader.buffered(), 2);
+    reader.push(&[0xbb, 0xcc, 1]);
+    assert_eq!(reader.decode::<TestIsa>(&decoder), StreamDecode::Decoded(TestInst { length: 4 }));
+    assert_eq!(Reader::<u64, u8>::total_offset(&mut reader), AddressDiff::from_const(7));
+    assert_eq!(reader.decode::<TestIsa>(&decoder), StreamDecode::NeedMoreInput);
+    reader.finish();
+    assert_eq!(reader.decode::<TestIsa>(&decoder), StreamDecode::Error(StandardDecodeError::ExhaustedInput));
+
+    // looking ahead past the data pushed so far doesn't make a bad instruction a partial one.
+    let decoder = TestIsaDecoder { lookahead: true, ..TestIsaDecoder::default() };
+    let mut reader = StreamReader::new();
+    reader.push(&[0xff]);
+    assert_eq!(reader.decode::<TestIsa>(&decoder), StreamDecode::Error(StandardDecodeError::InvalidOpcode));
+    let mut reader = StreamReader::new();
+    reader.push(&[1]);
+    assert_eq!(reader.decode::<TestIsa>(&decoder), StreamDecode::NeedMoreInput);
+    assert_eq!(Reader::<usize, u8>::total_offset(&mut reader), AddressDiff::from_const(0));
+}
+
+#[test]
+fn sweep_decodes_and_recovers() {
+    use yaxpeax_arch::{AddressDiff, BoundedReader, ErrorRecovery, IoReader, MemoryMap, Permissions, Region, StandardDecodeError, Sweep, U8Reader};
+    use length_prefixed::{TestIsa, TestInst, TestIsaDecoder};
+
+    let decoder = TestIsaDecoder::default();
+    let data = [1, 0xaa, 0xff, 0, 2, 0xbb];
+
+    let mut reader = U8Reader::new(&data);
+    let results: Vec<_> = Sweep::<TestIsa, _>::new(&decoder, &mut reader, 0x1000).collect();
+    assert_eq!(results, vec![
+        (0x1000, Ok(TestInst { length: 2 }), AddressDiff::from_const(2)),
+        (0x1002, Err(StandardDecodeError::InvalidOpcode), AddressDiff::from_const(1)),
+        (0x1003, Ok(TestInst { length: 1 }), AddressDiff::from_const(1)),
+        (0x1004, Err(StandardDecodeError::ExhaustedInput), AddressDiff::from_const(1)),
+        (0x1005, Err(StandardDecodeError::InvalidOpcode), AddressDiff::from_const(1)),
+    ]);
+
+    let mut reader = U8Reader::new(&data);
+    let mut sweep = Sweep::<TestIsa, _>::new(&decoder, &mut reader, 0x1000)
+        .with_recovery(ErrorRecovery::Stop);
+    let mut inst = TestInst::default();
+    assert_eq!(sweep.next_into(&mut inst), Some((0x1000, Ok(()), AddressDiff::from_const(2))));
+    assert_eq!(inst, TestInst { length: 2 });
+    assert_eq!(sweep.next_into(&mut inst), Some((0x1002, Err(StandardDecodeError::InvalidOpcode), AddressDiff::zero())));
+    assert_eq!(sweep.next_into(&mut inst), None);
+
+    // a reader that discards data as it goes can still be rewound to skip a failed instruction.
+    let mut reader = IoReader::with_capacity(8, &[1, 0, 1, 0, 1, 0, 3, 1, 2][..]);
+    let results: Vec<_> = Sweep::<TestIsa, _>::new(&decoder, &mut reader, 0).collect();
+    assert_eq!(results[3..], [
+        (6, Err(StandardDecodeError::ExhaustedInput), AddressDiff::from_const(1)),
+        (7, Ok(TestInst { length: 2 }), AddressDiff::from_const(2)),
+    ]);
+
+    // a length limit applies to each instruction, not to the sweep as a whole.
+    let data = [3, 0xaa, 0xbb, 0xcc, 3, 0xaa, 0xbb, 0xcc, 0];
+    let mut reader = BoundedReader::for_arch::<TestIsa>(U8Reader::new(&data));
+    let lengths: Vec<_> = Sweep::<TestIsa, _>::new(&decoder, &mut reader, 0).map(|(_, _, length)| length).collect();
+    assert_eq!(lengths, [AddressDiff::from_const(4), AddressDiff::from_const(4), AddressDiff::from_const(1)]);
+
+    // a hole in memory is reported, not mistaken for the end of input.
+    let regions = [
+        Region { base: 0x1000, data: &[1, 0xaa], permissions: Permissions::READ },
+        Region { base: 0x1003, data: &[0], permissions: Permissions::READ },
+    ];
+    let mut reader = MemoryMap::new(&regions).reader_at(0x1000);
+    let results: Vec<_> = Sweep::<TestIsa, _>::new(&decoder, &mut reader, 0x1000).collect();
+    assert_eq!(results, vec![
+        (0x1000, Ok(TestInst { length: 2 }), AddressDiff::from_const(2)),
+        (0x1002, Err(StandardDecodeError::ExhaustedInput), AddressDiff::zero()),
+    ]);
+}
+
+#[test]
+fn decode_many_stops_at_failed_instruction() {
+    use yaxpeax_arch::{AddressDiff, Decoder, IoReader, Reader, StandardDecodeError, U8Reader};
+    use length_prefixed::{TestInst, TestIsaDecoder};
+
+    let decoder = TestIsaDecoder::default();
+    let data = [1, 0xaa, 0, 2, 0xbb];
+    let mut reader = U8Reader::new(&data);
+    let mut insts: [TestInst; 4] = Default::default();
+
+    assert_eq!(decoder.decode_many(&mut reader, &mut insts[..1]), (1, None));
+    assert_eq!(insts[0], TestInst { length: 2 });
+
+    assert_eq!(decoder.decode_many(&mut reader, &mut insts), (1, Some(StandardDecodeError::ExhaustedInput)));
+    assert_eq!(insts[0], TestInst { length: 1 });
+    // the truncated instruction is left unread.
+    assert_eq!(Reader::<u64, u8>::total_offset(&mut reader), AddressDiff::from_const(3));
+
+    // the same holds for readers that discard data as they go, however often they refill.
+    let mut reader = IoReader::with_capacity(8, &[1, 0, 1, 0, 1, 0, 3, 1, 2][..]);
+    assert_eq!(decoder.decode_many(&mut reader, &mut insts), (3, Some(StandardDecodeError::ExhaustedInput)));
+    assert_eq!(Reader::<u64, u8>::total_offset(&mut reader), AddressDiff::from_const(6));
+}
+
+#[test]
+fn dyn_decoders_are_interchangeable() {
+    use yaxpeax_arch::{AddressDiff, ArchDecoder, DynDecoder, Reader, StandardDecodeError, U8Reader};
+    use yaxpeax_arch::{HexReader, IoReader};
+    use opcode_isas::{ByteIsa, ByteInst, HalfIsa};
+
+    let decoders: Vec<Box<dyn DynDecoder>> = vec![
+        Box::new(ArchDecoder::<ByteIsa>::new()),
+        Box::new(ArchDecoder::<HalfIsa>::new()),
+    ];
+    let data = [0x34, 0x12];
+
+    let inst = decoders[0].decode_slice(&data).unwrap();
+    assert_eq!(inst.to_string(), "byte 0x34");
+    assert_eq!(inst.len(), 1);
+    assert_eq!(inst.as_any().downcast_ref::<ByteInst>(), Some(&ByteInst { opcode: 0x34 }));
+
+    let mut reader = U8Reader::new(&data);
+    let inst = decoders[1].decode(&mut reader).unwrap();
+    assert_eq!(inst.to_string(), "half 0x1234");
+    assert_eq!(inst.len(), 2);
+    assert!(inst.well_defined());
+
+    let err = decoders[1].decode(&mut reader).unwrap_err();
+    assert!(err.data_exhausted());
+    assert_eq!(err.to_string(), "exhausted input");
+    assert_eq!(err.downcast_ref::<StandardDecodeError>(), Some(&StandardDecodeError::ExhaustedInput));
+
+    // any reader of bytes will do, and a word cut off by the end of input is left unread.
+    let mut reader = HexReader::new("34 12 56").unwrap();
+    assert_eq!(decoders[1].decode(&mut reader).unwrap().to_string(), "half 0x1234");
+    assert!(decoders[1].decode(&mut reader).unwrap_err().data_exhausted());
+    assert_eq!(decoders[0].decode(&mut reader).unwrap().to_string(), "byte 0x56");
+
+    let mut reader = IoReader::new(&[0x78, 0x56][..]);
+    assert_eq!(decoders[1].decode(&mut reader).unwrap().to_string(), "half 0x5678");
+    assert_eq!(Reader::<u64, u8>::total_offset(&mut reader), AddressDiff::from_const(2));
+}
+
+#[test]
+fn registry_finds_architectures_by_name() {
+    use yaxpeax_arch::{ArchEntry, ArchRegistry, RegistryError};
+    use opcode_isas::ByteIsa;
+
+    let mut registry = ArchRegistry::new();
+    registry.register(ArchEntry::new::<ByteIsa>("x86_64").with_aliases(&["amd64", "x64"])).unwrap();
+    registry.register(ArchEntry::new::<ByteIsa>("armv7").with_aliases(&["arm"]).with_description("32-bit arm")).unwrap();
+    registry.register(ArchEntry::new::<ByteIsa>("thumb")).unwrap();
+    assert_eq!(
+        registry.register(ArchEntry::new::<ByteIsa>("AMD-64")).unwrap_err(),
+        RegistryError::NameInUse { name: "AMD-64", registered: "x86_64" },
+    );
+
+    let names: Vec<&str> = registry.entries().iter().map(ArchEntry::name).collect();
+    assert_eq!(names, ["x86_64", "armv7", "thumb"]);
+    assert_eq!(registry.get("X86-64").unwrap().name(), "x86_64");
+    assert_eq!(registry.get("amd64").unwrap().name(), "x86_64");
+    assert_eq!(registry.get("ARM").unwrap().description(), "32-bit arm");
+    assert!(registry.get("mips").is_none());
+
+    let decoder = registry.decoder("thumb").unwrap();
+    assert_eq!(decoder.decode_slice(&[0x90]).unwrap().to_string(), "byte 0x90");
+
+    let suggestions: Vec<&str> = registry.search("arm7").iter().map(|entry| entry.name()).collect();
+    assert_eq!(suggestions, ["armv7"]);
+    let suggestions: Vec<&str> = registry.search("x86").iter().map(|entry| entry.name()).collect();
+    assert_eq!(suggestions, ["x86_64"]);
+    assert!(registry.search("sparc").is_empty());
+}
+
+#[test]
+fn arch_metadata_defaults_and_overrides() {
+    use yaxpeax_arch::{ArchEntry, ArchInfo, Endianness};
+    use opcode_isas::HalfIsa;
+
+    let info = ArchInfo {
+        name: "half",
+        endianness: Endianness::Little,
+        pointer_bits: 32,
+        word_bits: 16,
+        instruction_alignment: 2,
+        min_instruction_size: 2,
+        max_instruction_size: Some(2),
+    };
+    assert_eq!(ArchInfo::of::<HalfIsa>(), info);
+
+    let entry = ArchEntry::for_arch::<HalfIsa>();
+    assert_eq!(entry.name(), "half");
+    assert_eq!(entry.info(), Some(&info));
+    assert_eq!(entry.decoder().decode_slice(&[0x34, 0x12]).unwrap().to_string(), "half 0x1234");
+}
+
+#[test]
+fn control_flow_is_architecture_agnostic() {
+    use yaxpeax_arch::{ControlFlow, Effect};
+
+    // a toy instruction set with pc-relative branches, measured from the end of a two-byte
+    // instruction.
+    enum Inst {
+        Nop,
+        Jump(i8),
+        JumpIfZero(i8),
+        CallIndirect,
+        Ret,
+    }
+
+    impl ControlFlow for Inst {
+        type Address = u16;
+
+        fn effect(&self) -> Effect {
+            match self {
+                Inst::Nop => Effect::FallThrough,
+                Inst::Jump(_) | Inst::JumpIfZero(_) => Effect::Branch,
+                Inst::CallIndirect => Effect::Call,
+                Inst::Ret => Effect::Return,
+            }
+        }
+        fn is_conditional(&self) -> bool {
+            matches!(self, Inst::JumpIfZero(_))
+        }
+        fn target(&self, address: u16) -> Option<u16> {
+            match self {
+                Inst::Jump(rel) | Inst::JumpIfZero(rel) => Some(address.wrapping_add(2).wrapping_add(*rel as i16 as u16)),
+                _ => None,
+            }
+        }
+    }
+
+    fn successors<I: ControlFlow<Address = u16>>(inst: &I, address: u16) -> Vec<u16> {
+        let mut successors = Vec::new();
+        if inst.effect() != Effect::Call {
+            successors.extend(inst.target(address));
+        }
+        if inst.falls_through() {
+            successors.push(address + 2);
+        }
+        successors
+    }
+
+    assert_eq!(successors(&Inst::Nop, 0x100), [0x102]);
+    assert_eq!(successors(&Inst::Jump(-4), 0x100), [0xfe]);
+    assert_eq!(successors(&Inst::JumpIfZero(0x10), 0x100), [0x112, 0x102]);
+    assert_eq!(successors(&Inst::CallIndirect, 0x100), [0x102]);
+    assert_eq!(successors(&Inst::Ret, 0x100), []);
+}
+
+#[test]
+fn operands_are_architecture_agnostic() {
+    use yaxpeax_arch::{OperandInfo, OperandKind, Operands};
+
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    enum Operand {
+        Reg(u8),
+        Imm(i16),
+        Mem { base: u8, disp: i16 },
+        PcRel(i16),
+    }
+
+    impl OperandInfo for Operand {
+        fn kind(&self) -> OperandKind {
+            match self {
+                Operand::Reg(_) => OperandKind::Register,
+                Operand::Imm(_) => OperandKind::Immediate,
+                Operand::Mem { .. } => OperandKind::Memory,
+                Operand::PcRel(_) => OperandKind::PcRelative,
+            }
+        }
+        fn immediate_i64(&self) -> Option<i64> {
+            match self {
+                Operand::Imm(value) | Operand::PcRel(value) => Some(*value as i64),
+                _ => None,
+            }
+        }
+    }
+
+    struct Inst {
+        operands: [Operand; 3],
+        count: usize,
+    }
+
+    impl Operands for Inst {
+        type Operand = Operand;
+
+        fn operand_count(&self) -> usize {
+            self.count
+        }
+        fn operand(&self, index: usize) -> Option<Operand> {
+            self.operands[..self.count].get(index).copied()
+        }
+    }
+
+    fn constants<I: Operands>(inst: &I) -> Vec<u64> where I::Operand: OperandInfo {
+        inst.operands()
+            .filter(|op| op.kind() == OperandKind::Immediate)
+            .filter_map(|op| op.immediate_u64())
+            .collect()
+    }
+
+    let inst = Inst {
+        operands: [Operand::Mem { base: 1, disp: 8 }, Operand::Imm(-1), Operand::Reg(0)],
+        count: 2,
+    };
+    assert_eq!(inst.operands().len(), 2);
+    assert_eq!(inst.operand(2), None);
+    assert_eq!(constants(&inst), [u64::MAX]);
+    let kinds: Vec<OperandKind> = inst.operands().map(|op| op.kind()).collect();
+    assert_eq!(kinds, [OperandKind::Memory, OperandKind::Immediate]);
+    assert_eq!(Operand::PcRel(-4).immediate_i64(), Some(-4));
+    assert_eq!(Operand::Reg(3).immediate_u64(), None);
+}
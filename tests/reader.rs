@@ -1,24 +1,531 @@
-use yaxpeax_arch::{Reader, U8Reader, U16le, U32le};
+use yaxpeax_arch::{AddressDiff, Reader, U8Reader, U16le, U32le};
 
 #[test]
 fn reader_offset_is_words_not_bytes() {
     fn test_u16<T: Reader<u64, U16le>>(reader: &mut T) {
         reader.mark();
-        assert_eq!(reader.offset(), 0);
+        assert_eq!(reader.offset(), AddressDiff::from_const(0));
         reader.next().unwrap();
-        assert_eq!(reader.offset(), 1);
+        assert_eq!(reader.offset(), AddressDiff::from_const(1));
         reader.mark();
         reader.next().unwrap();
-        assert_eq!(reader.offset(), 1);
-        assert_eq!(reader.total_offset(), 2);
+        assert_eq!(reader.offset(), AddressDiff::from_const(1));
+        assert_eq!(reader.total_offset(), AddressDiff::from_const(2));
     }
     fn test_u32<T: Reader<u64, U32le>>(reader: &mut T) {
         reader.mark();
-        assert_eq!(reader.offset(), 0);
+        assert_eq!(reader.offset(), AddressDiff::from_const(0));
         reader.next().unwrap();
-        assert_eq!(reader.offset(), 1);
+        assert_eq!(reader.offset(), AddressDiff::from_const(1));
     }
 
     test_u16(&mut U8Reader::new(&[0x01, 0x02, 0x03, 0x04]));
     test_u32(&mut U8Reader::new(&[0x01, 0x02, 0x03, 0x04]));
 }
+
+#[cfg(feature = "std")]
+#[test]
+fn io_reader_refills_across_marks() {
+    use std::io::Read;
+    use yaxpeax_arch::{IoReader, ReadError, U16be};
+
+    // hand out at most three bytes per `read`, so words straddle refills.
+    struct Trickle<'a>(&'a [u8]);
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(self.0.len()).min(3);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    let data: Vec<u8> = (0..32).collect();
+    let mut reader = IoReader::with_capacity(8, Trickle(&data));
+    for i in 0..8u16 {
+        Reader::<u64, U16be>::mark(&mut reader);
+        assert_eq!(Reader::<u64, U16be>::next(&mut reader), Ok(U16be(((4 * i) << 8) | (4 * i + 1))));
+        assert_eq!(Reader::<u64, U16be>::next(&mut reader), Ok(U16be(((4 * i + 2) << 8) | (4 * i + 3))));
+        assert_eq!(Reader::<u64, U16be>::offset(&mut reader), AddressDiff::from_const(2));
+        assert_eq!(Reader::<u64, U16be>::total_offset(&mut reader), AddressDiff::from_const(2 * (i as u64 + 1)));
+    }
+    assert_eq!(Reader::<u64, u8>::next(&mut reader), Err(ReadError::Incomplete { read: 0, shortfall: 1 }));
+
+    struct Broken;
+    impl Read for Broken {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "no"))
+        }
+    }
+    let mut reader = IoReader::new(Broken);
+    let err = Reader::<u64, u8>::next(&mut reader).unwrap_err();
+    match &err {
+        ReadError::IOError(e) => {
+            assert_eq!(e.description(), "permission denied");
+            assert_eq!(e.io_error().map(|e| e.kind()), Some(std::io::ErrorKind::PermissionDenied));
+        }
+        other => panic!("expected an i/o error, got {:?}", other),
+    }
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+#[test]
+fn bit_reader_offsets_are_bits() {
+    use yaxpeax_arch::{BitOrder, BitReader, ReadError};
+
+    let data = [0b1011_0010, 0b0111_1111];
+
+    let mut reader = BitReader::new(&data, BitOrder::MsbFirst);
+    assert_eq!(reader.read_bits(3), Ok(0b101));
+    Reader::<u64, bool>::mark(&mut reader);
+    assert_eq!(reader.read_bits(7), Ok(0b100_1001));
+    assert_eq!(Reader::<u64, bool>::offset(&mut reader), AddressDiff::from_const(7));
+    assert_eq!(Reader::<u64, bool>::total_offset(&mut reader), AddressDiff::from_const(10));
+    assert_eq!(Reader::<u64, bool>::next(&mut reader), Ok(true));
+    assert_eq!(reader.read_bits(6), Err(ReadError::Incomplete { read: 0, shortfall: 1 }));
+    assert_eq!(reader.read_bits(5), Ok(0b11111));
+
+    let mut reader = BitReader::at_bit(&data, BitOrder::LsbFirst, 4);
+    assert_eq!(reader.read_bits(8), Ok(0b1111_1011));
+    assert_eq!(Reader::<u16, bool>::total_offset(&mut reader), AddressDiff::from_const(8));
+}
+
+#[test]
+fn peek_does_not_consume() {
+    fn check<T: Reader<u64, U16le>>(reader: &mut T) {
+        reader.next().unwrap();
+        reader.mark();
+        assert_eq!(reader.peek(), Ok(U16le(0x0403)));
+        let mut words = [U16le(0); 2];
+        reader.peek_n(&mut words).unwrap();
+        assert_eq!(words, [U16le(0x0403), U16le(0x0605)]);
+        assert_eq!(reader.offset(), AddressDiff::from_const(0));
+        assert_eq!(reader.total_offset(), AddressDiff::from_const(1));
+        assert_eq!(reader.peek_n(&mut [U16le(0); 3]), Err(yaxpeax_arch::ReadError::Incomplete { read: 2, shortfall: 1 }));
+        assert_eq!(reader.next(), Ok(U16le(0x0403)));
+    }
+
+    check(&mut U8Reader::new(&[0x01, 0x02, 0x03, 0x04, 0x05, 0x06]));
+    #[cfg(feature = "std")]
+    check(&mut yaxpeax_arch::IoReader::new(&[0x01, 0x02, 0x03, 0x04, 0x05, 0x06][..]));
+}
+
+#[test]
+fn checkpoint_and_restore() {
+    use yaxpeax_arch::ReadError;
+
+    fn check<T: Reader<u64, u8>>(reader: &mut T) {
+        reader.next().unwrap();
+        reader.mark();
+        assert_eq!(reader.next(), Ok(2));
+        let checkpoint = reader.checkpoint();
+        assert_eq!(reader.next(), Ok(3));
+        reader.mark();
+        assert_eq!(reader.next(), Ok(4));
+        reader.restore(checkpoint).unwrap();
+        assert_eq!(reader.offset(), AddressDiff::from_const(1));
+        assert_eq!(reader.total_offset(), AddressDiff::from_const(2));
+        assert_eq!(reader.next(), Ok(3));
+        reader.rewind_to_mark();
+        assert_eq!(reader.total_offset(), AddressDiff::from_const(1));
+        assert_eq!(reader.next(), Ok(2));
+        assert_eq!(reader.peek(), Ok(3));
+        assert_eq!(reader.next_n(&mut [0u8; 4]), Err(ReadError::Incomplete { read: 2, shortfall: 2 }));
+    }
+
+    check(&mut U8Reader::new(&[1, 2, 3, 4]));
+    #[cfg(feature = "std")]
+    check(&mut yaxpeax_arch::IoReader::new(&[1, 2, 3, 4][..]));
+}
+
+#[test]
+fn next_n_reports_partial_reads() {
+    use yaxpeax_arch::ReadError;
+
+    fn check<T: Reader<u64, U16le>>(reader: &mut T) {
+        let mut words = [U16le(0); 4];
+        assert_eq!(reader.next_n(&mut words), Err(ReadError::Incomplete { read: 2, shortfall: 2 }));
+        assert_eq!(&words[..2], &[U16le(0x0201), U16le(0x0403)]);
+        assert_eq!(reader.total_offset(), AddressDiff::from_const(2));
+        // the trailing byte is not a whole word, and stays unread.
+        assert_eq!(reader.next_n(&mut words), Err(ReadError::Incomplete { read: 0, shortfall: 4 }));
+        assert_eq!(reader.total_offset(), AddressDiff::from_const(2));
+    }
+
+    check(&mut U8Reader::new(&[0x01, 0x02, 0x03, 0x04, 0x05]));
+    #[cfg(feature = "std")]
+    check(&mut yaxpeax_arch::IoReader::new(&[0x01, 0x02, 0x03, 0x04, 0x05][..]));
+}
+
+#[test]
+fn odd_sized_and_mixed_endian_words() {
+    use yaxpeax_arch::{ReadError, U24le, U24be, U32pdp};
+
+    let data = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07];
+
+    let mut reader = U8Reader::new(&data);
+    assert_eq!(Reader::<u64, U24le>::next(&mut reader), Ok(U24le(0x030201)));
+    assert_eq!(Reader::<u64, U24le>::total_offset(&mut reader), AddressDiff::from_const(1));
+    let mut words = [U24le(0); 2];
+    assert_eq!(Reader::<u64, U24le>::next_n(&mut reader, &mut words), Err(ReadError::Incomplete { read: 1, shortfall: 1 }));
+    assert_eq!(words[0], U24le(0x060504));
+
+    let mut reader = U8Reader::new(&data);
+    let mut words = [U24be(0); 2];
+    assert_eq!(Reader::<u32, U24be>::next_n(&mut reader, &mut words), Ok(()));
+    assert_eq!(words, [U24be(0x010203), U24be(0x040506)]);
+
+    let mut reader = U8Reader::new(&data);
+    assert_eq!(Reader::<u16, U32pdp>::next(&mut reader), Ok(U32pdp(0x02010403)));
+    assert_eq!(Reader::<u16, U32pdp>::next(&mut reader), Err(ReadError::Incomplete { read: 0, shortfall: 1 }));
+}
+
+#[test]
+fn packed_words_count_word_offsets() {
+    use yaxpeax_arch::{BitOrder, Packing, PackedWordReader, ReadError};
+
+    // two 14-bit PIC16 words, padded to 16 bits: `movlw 0x55`, then garbage in the padding bits.
+    let mut reader = PackedWordReader::new(&[0x55, 0x30, 0xff, 0xff], 14, Packing::PaddedLe { bytes: 2 });
+    assert_eq!(Reader::<u16, u16>::next(&mut reader), Ok(0x3055));
+    assert_eq!(Reader::<u16, u16>::next(&mut reader), Ok(0x3fff));
+    assert_eq!(Reader::<u16, u16>::total_offset(&mut reader), AddressDiff::from_const(2));
+    assert_eq!(Reader::<u16, u16>::next(&mut reader), Err(ReadError::Incomplete { read: 0, shortfall: 1 }));
+
+    // 24-bit PIC24 words in four bytes each, addressed in 16-bit units.
+    let data = [0x01, 0x02, 0x03, 0x00, 0x04, 0x05, 0x06, 0x00];
+    let mut reader = PackedWordReader::new(&data, 24, Packing::PaddedLe { bytes: 4 })
+        .with_address_units(2);
+    Reader::<u32, u32>::mark(&mut reader);
+    assert_eq!(Reader::<u32, u32>::next(&mut reader), Ok(0x030201));
+    assert_eq!(Reader::<u32, u32>::offset(&mut reader), AddressDiff::from_const(2));
+    let mut words = [0u32; 2];
+    assert_eq!(Reader::<u32, u32>::next_n(&mut reader, &mut words), Err(ReadError::Incomplete { read: 1, shortfall: 1 }));
+    assert_eq!(words[0], 0x060504);
+    assert_eq!(Reader::<u32, u32>::total_offset(&mut reader), AddressDiff::from_const(4));
+    // 24-bit words do not fit in `u16` items.
+    Reader::<u32, u32>::rewind_to_mark(&mut reader);
+    assert_eq!(Reader::<u32, u16>::next(&mut reader), Err(ReadError::WordTooWide { width: 24 }));
+
+    // three densely packed 12-bit words in 36 bits, the last four bits unused.
+    let data = [0xab, 0xcd, 0xef, 0x12, 0x30];
+    let mut reader = PackedWordReader::new(&data, 12, Packing::Dense(BitOrder::MsbFirst));
+    let mut words = [0u16; 3];
+    assert_eq!(Reader::<u64, u16>::next_n(&mut reader, &mut words), Ok(()));
+    assert_eq!(words, [0xabc, 0xdef, 0x123]);
+    assert_eq!(Reader::<u64, u16>::next(&mut reader), Err(ReadError::Incomplete { read: 0, shortfall: 1 }));
+}
+
+#[test]
+fn memory_map_reads_by_address() {
+    use yaxpeax_arch::{MemoryMap, Permissions, ReadError, ReaderBuilder, Region};
+
+    let regions = [
+        Region { base: 0x1000, data: &[0x01, 0x02, 0x03], permissions: Permissions::READ | Permissions::EXECUTE },
+        Region { base: 0x1003, data: &[0x04, 0x05], permissions: Permissions::READ },
+        Region { base: 0x2000, data: &[0x06, 0x07], permissions: Permissions::READ },
+    ];
+    let map = MemoryMap::new(&regions);
+    assert!(map.region(0x1001).unwrap().permissions.contains(Permissions::EXECUTE));
+    assert!(map.region(0x1005).is_none());
+    let mut bytes = [0u8; 4];
+    let err = map.read(0x1003, &mut bytes).unwrap_err();
+    assert_eq!((err.address(), err.shortfall()), (Some(0x1005), Some(2)));
+
+    // the second word straddles the first two regions.
+    let mut reader = <MemoryMap as ReaderBuilder<u64, U16le>>::read_at(map, 0x1000);
+    assert_eq!(Reader::<u64, U16le>::next(&mut reader), Ok(U16le(0x0201)));
+    assert_eq!(Reader::<u64, U16le>::next(&mut reader), Ok(U16le(0x0403)));
+    assert_eq!(Reader::<u64, U16le>::next(&mut reader), Err(ReadError::Unmapped { address: 0x1005, read: 0, shortfall: 1 }));
+    assert_eq!(Reader::<u64, U16le>::total_offset(&mut reader), AddressDiff::from_const(2));
+
+    let mut reader = map.reader_at(0x1ffe);
+    assert_eq!(Reader::<u64, u8>::next(&mut reader), Err(ReadError::Unmapped { address: 0x1ffe, read: 0, shortfall: 1 }));
+
+    let mut reader = map.reader_at(0x1003);
+    let mut words = [U16le(0); 2];
+    assert_eq!(Reader::<u64, U16le>::next_n(&mut reader, &mut words), Err(ReadError::Unmapped { address: 0x1005, read: 1, shortfall: 1 }));
+    assert_eq!(words[0], U16le(0x0504));
+    assert_eq!(reader.address(), 0x1005);
+}
+
+#[test]
+fn provider_reader_caches_pages() {
+    use yaxpeax_arch::{IoError, MemoryProvider, ProviderReader, ReadError};
+
+    // maps `0x100..0x10a`, and fails outright for anything at or past `0x200`.
+    struct Target { reads: usize }
+
+    impl MemoryProvider for Target {
+        type Error = &'static str;
+
+        fn read(&mut self, addr: u64, buf: &mut [u8]) -> Result<usize, &'static str> {
+            self.reads += 1;
+            if addr >= 0x200 {
+                return Err("probe disconnected");
+            }
+            let mut count = 0;
+            for (i, byte) in buf.iter_mut().enumerate() {
+                let at = addr + i as u64;
+                if !(0x100..0x10a).contains(&at) {
+                    break;
+                }
+                *byte = at as u8;
+                count += 1;
+            }
+            Ok(count)
+        }
+    }
+
+    let mut target = Target { reads: 0 };
+    let mut cache = [0u8; 4];
+    {
+        let mut reader = ProviderReader::new(&mut target, &mut cache, 0x102);
+        // the first word is within one page, the second straddles two.
+        assert_eq!(Reader::<u64, U16le>::next(&mut reader), Ok(U16le(0x0302)));
+        assert_eq!(Reader::<u64, U16le>::next(&mut reader), Ok(U16le(0x0504)));
+        let mut words = [U16le(0); 3];
+        assert_eq!(Reader::<u64, U16le>::next_n(&mut reader, &mut words), Err(ReadError::Unmapped { address: 0x10a, read: 2, shortfall: 1 }));
+        assert_eq!(&words[..2], &[U16le(0x0706), U16le(0x0908)]);
+        assert_eq!(Reader::<u64, u8>::next(&mut reader), Err(ReadError::Unmapped { address: 0x10a, read: 0, shortfall: 1 }));
+        assert_eq!(Reader::<u64, U16le>::total_offset(&mut reader), AddressDiff::from_const(4));
+        assert_eq!(reader.address(), 0x10a);
+    }
+
+    // pages are aligned, so going back to earlier addresses in the cached page does not go back
+    // to the provider.
+    let mut reader = ProviderReader::new(&mut target, &mut cache, 0x105);
+    Reader::<u64, u8>::mark(&mut reader);
+    assert_eq!(Reader::<u64, u8>::next(&mut reader), Ok(0x05));
+    let checkpoint = Reader::<u64, u8>::checkpoint(&mut reader);
+    let mut words = [0u8; 2];
+    assert_eq!(Reader::<u64, u8>::next_n(&mut reader, &mut words), Ok(()));
+    assert_eq!(words, [0x06, 0x07]);
+    let reads = reader.get_ref().reads;
+    Reader::<u64, u8>::rewind_to_mark(&mut reader);
+    assert_eq!(Reader::<u64, u8>::next(&mut reader), Ok(0x05));
+    assert_eq!(Reader::<u64, U16le>::next(&mut reader), Ok(U16le(0x0706)));
+    Reader::<u64, u8>::restore(&mut reader, checkpoint).unwrap();
+    assert_eq!(Reader::<u64, U16le>::next(&mut reader), Ok(U16le(0x0706)));
+    assert_eq!(reader.get_ref().reads, reads);
+
+    let mut reader = ProviderReader::new(&mut target, &mut cache, 0x200);
+    assert_eq!(Reader::<u64, u8>::next(&mut reader), Err(ReadError::IOError(IoError::new("memory provider error"))));
+    assert_eq!(reader.take_error(), Some("probe disconnected"));
+}
+
+#[test]
+fn recording_reader_captures_instruction_words() {
+    use yaxpeax_arch::{ReadError, RecordingReader};
+
+    let data = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06];
+    let mut buf = [0u8; 3];
+    let mut reader = RecordingReader::new(U8Reader::new(&data), &mut buf);
+
+    Reader::<u64, u8>::mark(&mut reader);
+    assert_eq!(Reader::<u64, u8>::next(&mut reader), Ok(0x01));
+    assert_eq!(Reader::<u64, u8>::peek(&mut reader), Ok(0x02));
+    assert_eq!(reader.recorded(), &[0x01]);
+    let checkpoint = Reader::<u64, u8>::checkpoint(&mut reader);
+    let mut bytes = [0u8; 2];
+    assert_eq!(Reader::<u64, u8>::next_n(&mut reader, &mut bytes), Ok(()));
+    assert_eq!(reader.recorded(), &[0x01, 0x02, 0x03]);
+    assert!(!reader.truncated());
+    assert_eq!(Reader::<u64, u8>::restore(&mut reader, checkpoint), Ok(()));
+    assert_eq!(reader.recorded(), &[0x01]);
+
+    // the second instruction is longer than the buffer.
+    Reader::<u64, u8>::mark(&mut reader);
+    let mut bytes = [0u8; 6];
+    assert_eq!(Reader::<u64, u8>::next_n(&mut reader, &mut bytes), Err(ReadError::Incomplete { read: 5, shortfall: 1 }));
+    assert_eq!(reader.recorded(), &[0x02, 0x03, 0x04]);
+    assert!(reader.truncated());
+    assert_eq!(Reader::<u64, u8>::offset(&mut reader), AddressDiff::from_const(5));
+}
+
+#[test]
+fn bounded_reader_stops_at_limit() {
+    use yaxpeax_arch::{BoundedReader, ReadError};
+
+    let data = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06];
+    let mut reader = BoundedReader::new(U8Reader::new(&data), AddressDiff::from_const(2));
+    assert_eq!(Reader::<u64, U16le>::next(&mut reader), Ok(U16le(0x0201)));
+    assert_eq!(Reader::<u64, U16le>::next(&mut reader), Ok(U16le(0x0403)));
+    assert_eq!(Reader::<u64, U16le>::next(&mut reader), Err(ReadError::TooLong));
+    assert_eq!(Reader::<u64, U16le>::peek(&mut reader), Err(ReadError::TooLong));
+    assert_eq!(Reader::<u64, U16le>::offset(&mut reader), AddressDiff::from_const(2));
+
+    Reader::<u64, U16le>::mark(&mut reader);
+    let mut words = [U16le(0); 2];
+    assert_eq!(Reader::<u64, U16le>::next_n(&mut reader, &mut words), Err(ReadError::Incomplete { read: 1, shortfall: 1 }));
+    assert_eq!(Reader::<u64, u8>::next(&mut reader), Err(ReadError::TooLong));
+}
+
+#[test]
+fn readers_at_usize_addresses() {
+    use yaxpeax_arch::{BitOrder, BitReader, IoReader, MemoryMap, Packing, PackedWordReader, Permissions, ReaderBuilder, Region};
+
+    let data: &[u8] = &[0x01, 0x02, 0x03, 0x04, 0x05];
+    let mut reader = <&[u8] as ReaderBuilder<usize, U16le>>::read_at(data, 1usize);
+    assert_eq!(Reader::<usize, U16le>::next(&mut reader), Ok(U16le(0x0302)));
+    assert_eq!(Reader::<usize, U16le>::next(&mut reader), Ok(U16le(0x0504)));
+    assert_eq!(Reader::<usize, U16le>::total_offset(&mut reader), AddressDiff::from_const(2usize));
+
+    let mut reader = IoReader::new(data);
+    assert_eq!(Reader::<usize, U16le>::next(&mut reader), Ok(U16le(0x0201)));
+    assert_eq!(Reader::<usize, U16le>::total_offset(&mut reader), AddressDiff::from_const(1usize));
+
+    let regions = [Region { base: 0x1000, data, permissions: Permissions::READ }];
+    let mut reader = <MemoryMap as ReaderBuilder<usize, U16le>>::read_at(MemoryMap::new(&regions), 0x1001usize);
+    assert_eq!(Reader::<usize, U16le>::next(&mut reader), Ok(U16le(0x0302)));
+    assert_eq!(Reader::<usize, U16le>::total_offset(&mut reader), AddressDiff::from_const(1usize));
+
+    let mut reader = BitReader::new(data, BitOrder::MsbFirst);
+    assert_eq!(Reader::<usize, bool>::next_n(&mut reader, &mut [false; 8]), Ok(()));
+    assert_eq!(Reader::<usize, bool>::total_offset(&mut reader), AddressDiff::from_const(8usize));
+
+    let mut reader = PackedWordReader::new(data, 12, Packing::PaddedLe { bytes: 2 });
+    assert_eq!(Reader::<usize, u16>::next(&mut reader), Ok(0x201));
+    assert_eq!(Reader::<usize, u16>::total_offset(&mut reader), AddressDiff::from_const(1usize));
+}
+
+#[test]
+fn bi_endian_words_follow_reader_endianness() {
+    use yaxpeax_arch::{Endianness, IoReader, MemoryMap, MemoryProvider, Permissions, ProviderReader, ReaderBuilder, Region, StreamReader, U32bi};
+
+    fn decode<T: Reader<u64, U32bi>>(reader: &mut T) -> (U32bi, U32bi) {
+        let first = reader.next().unwrap();
+        let mut rest = [U32bi(0)];
+        reader.next_n(&mut rest).unwrap();
+        (first, rest[0])
+    }
+
+    let data = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+    assert_eq!(decode(&mut U8Reader::new(&data)), (U32bi(0x04030201), U32bi(0x08070605)));
+    let mut reader = U8Reader::with_endianness(&data, Endianness::Big);
+    assert_eq!(reader.endianness(), Endianness::Big);
+    assert_eq!(decode(&mut reader), (U32bi(0x01020304), U32bi(0x05060708)));
+
+    assert_eq!(decode(&mut IoReader::new(&data[..])), (U32bi(0x04030201), U32bi(0x08070605)));
+    let mut reader = IoReader::new(&data[..]).with_endianness(Endianness::Big);
+    assert_eq!(decode(&mut reader), (U32bi(0x01020304), U32bi(0x05060708)));
+
+    let regions = [Region { base: 0x1000, data: &data, permissions: Permissions::READ }];
+    let map = MemoryMap::new(&regions).with_endianness(Endianness::Big);
+    let mut reader = <MemoryMap as ReaderBuilder<u64, U32bi>>::read_at(map, 0x1000);
+    assert_eq!(reader.endianness(), Endianness::Big);
+    assert_eq!(decode(&mut reader), (U32bi(0x01020304), U32bi(0x05060708)));
+
+    struct Flat<'a>(&'a [u8]);
+    impl MemoryProvider for Flat<'_> {
+        type Error = ();
+
+        fn read(&mut self, addr: u64, buf: &mut [u8]) -> Result<usize, ()> {
+            let available = self.0.get(addr as usize..).unwrap_or(&[]);
+            let count = core::cmp::min(available.len(), buf.len());
+            buf[..count].copy_from_slice(&available[..count]);
+            Ok(count)
+        }
+    }
+    let mut cache = [0u8; 4];
+    let mut reader = ProviderReader::new(Flat(&data), &mut cache, 0).with_endianness(Endianness::Big);
+    assert_eq!(decode(&mut reader), (U32bi(0x01020304), U32bi(0x05060708)));
+
+    let mut reader = StreamReader::with_endianness(Endianness::Big);
+    reader.push(&data);
+    assert_eq!(decode(&mut reader), (U32bi(0x01020304), U32bi(0x05060708)));
+}
+
+#[test]
+fn hex_reader_parses_common_notations() {
+    use yaxpeax_arch::{Endianness, HexError, HexReader, ReadError, U16bi};
+
+    for text in &["48 89 e5", "4889e5", "\\x48\\x89\\xe5", "0x48, 0x89, 0xE5"] {
+        let mut reader = HexReader::new(text).unwrap();
+        let mut bytes = [0u8; 3];
+        assert_eq!(Reader::<u64, u8>::next_n(&mut reader, &mut bytes), Ok(()), "{}", text);
+        assert_eq!(bytes, [0x48, 0x89, 0xe5], "{}", text);
+        assert_eq!(Reader::<u64, u8>::next(&mut reader), Err(ReadError::Incomplete { read: 0, shortfall: 1 }));
+    }
+
+    let mut reader = HexReader::new("01 02 03 04 05").unwrap();
+    assert_eq!(Reader::<u64, U16le>::next(&mut reader), Ok(U16le(0x0201)));
+    Reader::<u64, U16le>::mark(&mut reader);
+    assert_eq!(Reader::<u64, U16le>::peek(&mut reader), Ok(U16le(0x0403)));
+    assert_eq!(Reader::<u64, U16le>::next(&mut reader), Ok(U16le(0x0403)));
+    assert_eq!(Reader::<u64, U16le>::offset(&mut reader), AddressDiff::from_const(1));
+    Reader::<u64, U16le>::rewind_to_mark(&mut reader);
+    assert_eq!(Reader::<u64, u8>::next(&mut reader), Ok(0x03));
+
+    let mut reader = HexReader::new("01 02 03 04").unwrap();
+    assert_eq!(Reader::<u64, U16bi>::next(&mut reader), Ok(U16bi(0x0201)));
+    let mut reader = HexReader::with_endianness("01 02 03 04", Endianness::Big).unwrap();
+    assert_eq!(Reader::<u64, U16bi>::peek(&mut reader), Ok(U16bi(0x0102)));
+    let mut words = [U16bi(0); 2];
+    assert_eq!(Reader::<u64, U16bi>::next_n(&mut reader, &mut words), Ok(()));
+    assert_eq!(words, [U16bi(0x0102), U16bi(0x0304)]);
+
+    assert_eq!(HexReader::new("48 8z").err(), Some(HexError::InvalidCharacter { column: 5, character: 'z' }));
+    assert_eq!(HexReader::new("48 89e").err(), Some(HexError::IncompleteByte { column: 6 }));
+    assert_eq!(HexReader::new("\\x48\\x").err(), Some(HexError::IncompleteByte { column: 5 }));
+}
+
+#[test]
+fn slice_reader_fast_path() {
+    use yaxpeax_arch::ReadError;
+
+    // skip any run of 0x66 prefixes, taking the slice fast path when there is one.
+    fn skip_prefixes<T: Reader<u64, u8>>(reader: &mut T) -> usize {
+        if let Some(slice) = reader.as_slice_reader() {
+            let count = slice.remaining().iter().take_while(|b| **b == 0x66).count();
+            slice.advance(count).unwrap();
+            return count;
+        }
+        let mut count = 0;
+        while reader.peek() == Ok(0x66) {
+            reader.next().unwrap();
+            count += 1;
+        }
+        count
+    }
+
+    let data = [0x66, 0x66, 0x90];
+    let mut reader = U8Reader::new(&data);
+    assert_eq!(skip_prefixes(&mut reader), 2);
+    assert_eq!(Reader::<u64, u8>::total_offset(&mut reader), AddressDiff::from_const(2));
+    assert_eq!(Reader::<u64, u8>::next(&mut reader), Ok(0x90));
+
+    let mut reader = yaxpeax_arch::HexReader::new("66 66 90").unwrap();
+    assert_eq!(skip_prefixes(&mut reader), 2);
+
+    let mut reader = U8Reader::new(&data);
+    let slice = Reader::<u64, u8>::as_slice_reader(&mut reader).unwrap();
+    assert_eq!(slice.advance(4), Err(ReadError::Incomplete { read: 0, shortfall: 1 }));
+    assert_eq!(slice.remaining(), &data);
+}
+
+#[test]
+fn dyn_word_reader_reads_words_in_order() {
+    use yaxpeax_arch::{DynWordReader, Endianness, ReadError, U16bi};
+
+    let data = [0x01, 0x02, 0x03, 0x04, 0x05];
+    let mut bytes = U8Reader::new(&data);
+    let mut reader = DynWordReader::new(&mut bytes, Endianness::Big);
+    // a short read fills in, and puts in order, only the words it read.
+    let mut words = [U16bi(0xaabb); 3];
+    assert_eq!(Reader::<u64, U16bi>::peek_n(&mut reader, &mut words), Err(ReadError::Incomplete { read: 2, shortfall: 1 }));
+    assert_eq!(words, [U16bi(0x0102), U16bi(0x0304), U16bi(0xaabb)]);
+    let mut words = [U16bi(0xaabb); 3];
+    assert_eq!(Reader::<u64, U16bi>::next_n(&mut reader, &mut words), Err(ReadError::Incomplete { read: 2, shortfall: 1 }));
+    assert_eq!(words, [U16bi(0x0102), U16bi(0x0304), U16bi(0xaabb)]);
+    // the trailing fraction of a word is left unread.
+    assert_eq!(Reader::<u64, u8>::next(&mut reader), Ok(0x05));
+
+    // reads too long to buffer on the stack work the same.
+    let data: Vec<u8> = (0..200).collect();
+    let mut bytes = U8Reader::new(&data);
+    let mut reader = DynWordReader::new(&mut bytes, Endianness::Little);
+    let mut words = [U16le(0); 100];
+    assert_eq!(Reader::<u64, U16le>::next_n(&mut reader, &mut words), Ok(()));
+    assert_eq!(words[99], U16le(0xc7c6));
+    assert_eq!(Reader::<u64, U16le>::offset(&mut reader), AddressDiff::from_const(100));
+}